@@ -0,0 +1,424 @@
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    autopilot::Autopilot,
+    bird::Bird,
+    config::{ConfigError, GameConfig},
+    level::LevelCompleteEvent,
+    progress::{Progress, ProgressLabel},
+    replay::ReplayPlayback,
+    storage, ui, DeathCause, DiedEvent, FlapEvent, GameResetEvent, IncreaseScoreEvent,
+};
+
+/// Path of the achievement definitions, relative to the assets folder.
+pub const ACHIEVEMENTS_PATH: &str = "config/achievements.ron";
+
+/// Seconds each achievement toast stays on screen.
+const TOAST_SECONDS: f32 = 4.0;
+
+/// Seconds a toast takes to fade out at the end.
+const TOAST_FADE_SECONDS: f32 = 0.5;
+
+/// Unlocks the [`AchievementList`] achievements as their goals are met, saving them between
+/// sessions and announcing each with a toast.
+///
+/// The run and flap goals are read from the [`Progress`] totals, so the
+/// [`crate::progress::ProgressPlugin`] has to be added too.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        let path = storage::data_path("achievements.ron");
+        let unlocked: UnlockedAchievements = path
+            .as_deref()
+            .map(storage::load_or_default)
+            .unwrap_or_default();
+
+        app.insert_resource(AchievementList::load_or_default())
+            .insert_resource(unlocked)
+            .insert_resource(AchievementsPath(path))
+            .add_event::<AchievementUnlockedEvent>()
+            .add_startup_system(setup_toasts)
+            .add_system(track_achievements.after(ProgressLabel))
+            .add_system(show_toasts)
+            .add_system(fade_toasts);
+    }
+}
+
+/// The achievements that can be unlocked, in the order they're listed.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AchievementList {
+    pub achievements: Vec<Achievement>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Achievement {
+    /// Saved to remember the achievement, so it shouldn't change once released.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub goal: Goal,
+}
+
+/// What has to happen for an achievement to unlock.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Goal {
+    /// Reaching a score in a single run.
+    Score(u32),
+    /// Crashing into the nth pipe pair of a run, counting from 1.
+    DieOnPipe(u32),
+    /// Finishing a number of runs over all sessions, whether by dying or completing a level.
+    Runs(u32),
+    /// Flapping a number of times over all sessions.
+    Flaps(u64),
+    /// Passing pipes in a row without flapping above a height, from -1 at the bottom to 1 at
+    /// the top of the playfield.
+    PipesWithoutFlappingAbove { pipes: u32, height: f32 },
+}
+
+impl AchievementList {
+    /// Loads and validates the definitions from the assets folder.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_ron(&fs::read_to_string(storage::asset_path(ACHIEVEMENTS_PATH))?)
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, ConfigError> {
+        let list: AchievementList = ron::de::from_str(contents)?;
+        list.validate()?;
+        Ok(list)
+    }
+
+    /// Loads the definitions, logging any error and falling back to having no achievements.
+    pub fn load_or_default() -> Self {
+        AchievementList::load().unwrap_or_else(|err| {
+            warn!("achievements disabled: {}", err);
+            AchievementList::default()
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (i, achievement) in self.achievements.iter().enumerate() {
+            if self.achievements[..i]
+                .iter()
+                .any(|other| other.id == achievement.id)
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "there is more than one achievement with the id {}",
+                    achievement.id
+                )));
+            }
+            if matches!(achievement.goal, Goal::DieOnPipe(0)) {
+                return Err(ConfigError::Invalid(format!(
+                    "achievement {} counts pipes from 1, not 0",
+                    achievement.id
+                )));
+            }
+            if let Goal::PipesWithoutFlappingAbove { height, .. } = achievement.goal {
+                if !(-1.0..=1.0).contains(&height) {
+                    return Err(ConfigError::Invalid(format!(
+                        "achievement {} has a height outside -1 to 1",
+                        achievement.id
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The achievements unlocked so far.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnlockedAchievements {
+    /// Ids of the unlocked achievements.
+    pub ids: BTreeSet<String>,
+}
+
+/// Sent when an achievement is unlocked, with its index in the [`AchievementList`].
+pub struct AchievementUnlockedEvent(pub usize);
+
+/// Where the achievements are saved, if the platform has a data directory.
+struct AchievementsPath(Option<PathBuf>);
+
+impl AchievementsPath {
+    fn save(&self, unlocked: &UnlockedAchievements) {
+        if let Some(path) = &self.0 {
+            if let Err(err) = storage::save(path, unlocked) {
+                error!("failed to save achievements: {}", err);
+            }
+        }
+    }
+}
+
+/// What happened so far in the current run.
+#[derive(Default)]
+struct RunStats {
+    score: u32,
+    death: Option<DeathCause>,
+    /// Pipes passed since the last flap above each achievement's height, by achievement index.
+    streaks: Vec<u32>,
+}
+
+impl RunStats {
+    /// Whether the goal of the achievement at `index` is met by this run and the `totals` so far.
+    fn meets(&self, index: usize, goal: Goal, totals: &Progress) -> bool {
+        match goal {
+            Goal::Score(score) => self.score >= score,
+            // Pipes can't be passed without scoring, so the score tells which pair was hit
+            Goal::DieOnPipe(pipe) => self.death == Some(DeathCause::Pipe) && self.score + 1 == pipe,
+            Goal::Runs(runs) => totals.runs >= runs,
+            Goal::Flaps(flaps) => totals.flaps >= flaps,
+            Goal::PipesWithoutFlappingAbove { pipes, .. } => self.streaks[index] >= pipes,
+        }
+    }
+}
+
+fn track_achievements(
+    mut flap_events: EventReader<FlapEvent>,
+    mut increase_score_events: EventReader<IncreaseScoreEvent>,
+    mut died_events: EventReader<DiedEvent>,
+    mut game_reset_events: EventReader<GameResetEvent>,
+    mut level_complete_events: EventReader<LevelCompleteEvent>,
+    autopilot: Res<Autopilot>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    config: Res<GameConfig>,
+    list: Res<AchievementList>,
+    totals: Res<Progress>,
+    mut unlocked: ResMut<UnlockedAchievements>,
+    path: Res<AchievementsPath>,
+    bird: Query<&Transform, With<Bird>>,
+    mut run: Local<RunStats>,
+    mut achievement_unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+    if game_reset_events.iter().next().is_some() || run.streaks.len() != list.achievements.len() {
+        *run = RunStats {
+            score: 0,
+            death: None,
+            streaks: vec![0; list.achievements.len()],
+        };
+    }
+
+    let flaps = flap_events.iter().count() as u64;
+    let points = increase_score_events.iter().count() as u32;
+    let death = died_events.iter().next().map(|died| died.cause);
+    let level_complete = level_complete_events.iter().next().is_some();
+    let run_over = death.is_some() || level_complete;
+    // Achievements have to be earned by the player, not the autopilot or a replay of a run
+    // that already counted
+    if autopilot.enabled || replay_playback.is_some() || (flaps == 0 && points == 0 && !run_over) {
+        return;
+    }
+
+    if flaps > 0 {
+        let bird_y = bird
+            .get_single()
+            .map(|bird| bird.translation.y)
+            .unwrap_or_default();
+        for (achievement, streak) in list.achievements.iter().zip(&mut run.streaks) {
+            if let Goal::PipesWithoutFlappingAbove { height, .. } = achievement.goal {
+                if bird_y > height * config.window_height / 2.0 {
+                    *streak = 0;
+                }
+            }
+        }
+    }
+    run.score += points;
+    for streak in run.streaks.iter_mut() {
+        *streak += points;
+    }
+    if death.is_some() {
+        run.death = death;
+    }
+
+    let mut newly_unlocked = false;
+    for (index, achievement) in list.achievements.iter().enumerate() {
+        if unlocked.ids.contains(&achievement.id) {
+            continue;
+        }
+
+        if run.meets(index, achievement.goal, &totals) {
+            info!("unlocked achievement {}", achievement.name);
+            unlocked.ids.insert(achievement.id.clone());
+            achievement_unlocked_events.send(AchievementUnlockedEvent(index));
+            newly_unlocked = true;
+        }
+    }
+
+    if newly_unlocked {
+        path.save(&unlocked);
+    }
+}
+
+/// Column of toasts in the top right corner.
+#[derive(Component)]
+struct ToastColumn;
+
+#[derive(Component)]
+struct Toast {
+    /// Seconds the toast has been shown for.
+    age: f32,
+}
+
+fn setup_toasts(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    right: Val::Px(15.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(ToastColumn);
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    list: Res<AchievementList>,
+    mut achievement_unlocked_events: EventReader<AchievementUnlockedEvent>,
+    column: Query<Entity, With<ToastColumn>>,
+) {
+    let column = match column.get_single() {
+        Ok(column) => column,
+        Err(_) => return,
+    };
+
+    for AchievementUnlockedEvent(index) in achievement_unlocked_events.iter() {
+        let achievement = match list.achievements.get(*index) {
+            Some(achievement) => achievement,
+            None => continue,
+        };
+
+        let title_font = asset_server.load("fonts/FiraSans-Bold.ttf");
+        let value_font = asset_server.load("fonts/FiraMono-Medium.ttf");
+        let text = |value: String, font: &Handle<Font>, font_size: f32, color: Color| TextBundle {
+            text: ui::text(value, font, font_size, color),
+            ..Default::default()
+        };
+
+        commands.entity(column).with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        margin: Rect::all(Val::Px(4.0)),
+                        padding: Rect::all(Val::Px(12.0)),
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                    ..Default::default()
+                })
+                .insert(Toast { age: 0.0 })
+                .with_children(|parent| {
+                    parent.spawn_bundle(text(
+                        format!("Achievement unlocked: {}", achievement.name),
+                        &title_font,
+                        24.0,
+                        Color::GOLD,
+                    ));
+                    parent.spawn_bundle(text(
+                        achievement.description.clone(),
+                        &value_font,
+                        18.0,
+                        Color::WHITE,
+                    ));
+                });
+        });
+    }
+}
+
+/// Fades toasts out at the end of their time on screen, then removes them.
+fn fade_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &mut UiColor, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, mut toast, mut color, children) in toasts.iter_mut() {
+        // Real time, so toasts still go away while paused
+        toast.age += time.delta_seconds();
+        if toast.age >= TOAST_SECONDS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let fade_start = TOAST_SECONDS - TOAST_FADE_SECONDS;
+        if toast.age > fade_start {
+            let alpha = 1.0 - (toast.age - fade_start) / TOAST_FADE_SECONDS;
+            color.0.set_a(0.7 * alpha);
+            for child in children.iter() {
+                if let Ok(mut text) = texts.get_mut(*child) {
+                    for section in text.sections.iter_mut() {
+                        section.style.color.set_a(alpha);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score: u32, death: Option<DeathCause>, streaks: Vec<u32>) -> RunStats {
+        RunStats {
+            score,
+            death,
+            streaks,
+        }
+    }
+
+    #[test]
+    fn pipes_are_counted_from_one_and_only_pipe_crashes_count() {
+        let totals = Progress::default();
+        let first = Goal::DieOnPipe(1);
+        assert!(!run(0, None, vec![0]).meets(0, first, &totals));
+        assert!(run(0, Some(DeathCause::Pipe), vec![0]).meets(0, first, &totals));
+        assert!(!run(0, Some(DeathCause::Bounds), vec![0]).meets(0, first, &totals));
+        assert!(run(2, Some(DeathCause::Pipe), vec![0]).meets(0, Goal::DieOnPipe(3), &totals));
+    }
+
+    #[test]
+    fn scores_count_per_run_and_runs_and_flaps_over_all_sessions() {
+        let totals = Progress {
+            runs: 3,
+            flaps: 100,
+            ..Progress::default()
+        };
+        let run = run(5, None, vec![0]);
+        assert!(run.meets(0, Goal::Score(5), &totals));
+        assert!(!run.meets(0, Goal::Score(6), &totals));
+        assert!(run.meets(0, Goal::Runs(3), &totals));
+        assert!(!run.meets(0, Goal::Runs(4), &totals));
+        assert!(run.meets(0, Goal::Flaps(100), &totals));
+        assert!(!run.meets(0, Goal::Flaps(101), &totals));
+    }
+
+    #[test]
+    fn streaks_are_counted_per_achievement() {
+        let totals = Progress::default();
+        let goal = Goal::PipesWithoutFlappingAbove {
+            pipes: 10,
+            height: 0.0,
+        };
+        // The run has passed 12 pipes, but flapped too high for the second achievement 3 ago
+        let run = run(12, None, vec![12, 3]);
+        assert!(run.meets(0, goal, &totals));
+        assert!(!run.meets(1, goal, &totals));
+    }
+}
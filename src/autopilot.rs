@@ -0,0 +1,157 @@
+use bevy::{input::InputSystem, prelude::*};
+use heron::prelude::*;
+
+use crate::{
+    bird::Bird,
+    config::GameConfig,
+    controls::{ActionInput, ControlsLabel, InputAction},
+    game_state::GameState,
+    level::CurrentLevel,
+    menu::MenuScreen,
+    pipes::{next_pipe_gap, PipePair},
+    GameResetEvent,
+};
+
+/// How far ahead in seconds the bird's fall is predicted.
+const LOOKAHEAD: f32 = 0.15;
+/// How far below the gap centre the bird may fall before flapping.
+const FLAP_MARGIN: f32 = 25.0;
+/// Frames to linger on the death screen before restarting.
+const RESTART_DELAY: u32 = 60;
+/// Seconds the title or Waiting screen has to be left alone before the attract mode starts.
+const ATTRACT_DELAY: f32 = 20.0;
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autopilot>().add_system_to_stage(
+            CoreStage::PreUpdate,
+            autopilot.label(ControlsLabel).after(InputSystem),
+        );
+    }
+}
+
+/// Shows off the game with the autopilot while nobody is playing, like an arcade attract mode.
+///
+/// Only added to windowed games that aren't automated already, as it needs [`MenuScreen`].
+pub struct AttractModePlugin;
+
+impl Plugin for AttractModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, attract_mode.after(ControlsLabel));
+    }
+}
+
+/// Lets the game play itself, starting and restarting runs on its own.
+///
+/// The autopilot presses [`InputAction::Flap`], so its flaps go through the same jump impulse and
+/// [`crate::FlapEvent`] as the player's.
+#[derive(Default)]
+pub struct Autopilot {
+    pub enabled: bool,
+}
+
+fn autopilot(
+    autopilot: Res<Autopilot>,
+    config: Res<GameConfig>,
+    game_state: Res<GameState>,
+    mut action_input: ResMut<ActionInput>,
+    bird: Query<(&Transform, &Velocity), With<Bird>>,
+    pipe_pairs: Query<&Transform, With<PipePair>>,
+    mut dead_frames: Local<u32>,
+) {
+    if !autopilot.enabled {
+        return;
+    }
+
+    match *game_state {
+        GameState::Menu | GameState::Paused | GameState::LevelComplete | GameState::Editor => {}
+        GameState::Waiting => action_input.press(InputAction::Flap),
+        GameState::Playing => {
+            if let Ok((transform, velocity)) = bird.get_single() {
+                let position = transform.translation;
+                let target = next_pipe_gap(position.x, config.pipe_width, pipe_pairs.iter())
+                    .map_or(0.0, |gap| gap.y);
+                let predicted = position.y + velocity.linear.y * LOOKAHEAD;
+
+                if velocity.linear.y <= 0.0 && predicted < target - FLAP_MARGIN {
+                    action_input.press(InputAction::Flap);
+                }
+            }
+        }
+        GameState::Dead => {
+            *dead_frames += 1;
+            if *dead_frames >= RESTART_DELAY {
+                *dead_frames = 0;
+                action_input.press(InputAction::Flap);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct AttractMode {
+    idle: f32,
+    demo: bool,
+    from_menu: bool,
+}
+
+fn attract_mode(
+    time: Res<Time>,
+    menu_screen: Res<MenuScreen>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut autopilot: ResMut<Autopilot>,
+    mut game_state: ResMut<GameState>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut action_input: ResMut<ActionInput>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+    mut attract: Local<AttractMode>,
+) {
+    let player_input = keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+        || touches.iter_just_pressed().next().is_some()
+        || gamepad_input.get_just_pressed().next().is_some();
+
+    if attract.demo {
+        if player_input {
+            // The press only hands control back, it doesn't also flap or navigate the menu
+            action_input.clear();
+            autopilot.enabled = false;
+            *game_state = if attract.from_menu {
+                GameState::Menu
+            } else {
+                GameState::Waiting
+            };
+            game_reset_events.send(GameResetEvent);
+            *attract = AttractMode::default();
+        }
+        return;
+    }
+
+    let idle_screen = match *game_state {
+        GameState::Menu => *menu_screen == MenuScreen::Main,
+        GameState::Waiting => true,
+        _ => false,
+    };
+    if player_input || !idle_screen || game_state.is_changed() {
+        attract.idle = 0.0;
+        return;
+    }
+
+    attract.idle += time.delta_seconds();
+    if attract.idle >= ATTRACT_DELAY {
+        // Demo runs stay out of the scores and progress like every other autopilot run
+        autopilot.enabled = true;
+        attract.demo = true;
+        attract.from_menu = matches!(*game_state, GameState::Menu);
+        if attract.from_menu {
+            *current_level = CurrentLevel::default();
+            *game_state = GameState::Waiting;
+            game_reset_events.send(GameResetEvent);
+        }
+    }
+}
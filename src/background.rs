@@ -0,0 +1,234 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{MainCamera, Playfield},
+    config::{ConfigError, GameConfig},
+    difficulty::{Difficulty, DifficultyLabel},
+    game_state::GameState,
+    simulation::Timestep,
+    storage,
+    theme::Theme,
+};
+
+/// Path of the default background layers config, relative to the assets folder.
+pub const BACKGROUND_CONFIG_PATH: &str = "config/background.ron";
+
+/// Depth of the backmost layer. Each following layer is drawn one unit in front of the last,
+/// all of them behind the playfield.
+const BACKGROUND_Z: f32 = -100.0;
+
+/// Draws the theme's [`BackgroundConfig`] layers behind the playfield, and scrolls them and any
+/// other [`TiledStrip`] while playing.
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundConfig>()
+            .init_resource::<BackgroundScroll>()
+            .add_system(load_background)
+            .add_system(spawn_layers)
+            .add_system(scroll_strips.after(DifficultyLabel));
+    }
+}
+
+/// Parallax layers of the background, from back to front.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackgroundConfig {
+    pub layers: Vec<BackgroundLayer>,
+}
+
+/// An image repeated horizontally across the window.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackgroundLayer {
+    /// Path of the image, relative to the assets folder. It should tile seamlessly.
+    pub image: String,
+    /// Fraction of the pipe speed the layer scrolls at, from 0 for still to 1 for the pipes' pace.
+    pub speed: f32,
+    /// Width of each tile of the image.
+    pub width: f32,
+    pub height: f32,
+    /// Height of the layer's centre, where 0 is the middle of the playfield.
+    #[serde(default)]
+    pub y: f32,
+    /// Colour the image is multiplied by, as red, green and blue between 0 and 1.
+    #[serde(default = "default_tint")]
+    pub tint: (f32, f32, f32),
+}
+
+fn default_tint() -> (f32, f32, f32) {
+    (1.0, 1.0, 1.0)
+}
+
+impl BackgroundConfig {
+    /// Loads and validates the config at `path` in the assets folder.
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        Self::load_from(&storage::asset_path(path))
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, ConfigError> {
+        let config: BackgroundConfig = ron::de::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the config, logging any error and falling back to a plain background.
+    pub fn load_or_default(path: &str) -> Self {
+        BackgroundConfig::load(path).unwrap_or_else(|err| {
+            warn!("using no background layers from {}: {}", path, err);
+            BackgroundConfig::default()
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (i, layer) in self.layers.iter().enumerate() {
+            let positive = |value: f32| value.is_finite() && value > 0.0;
+            if !(positive(layer.width) && positive(layer.height)) {
+                return Err(ConfigError::Invalid(format!(
+                    "background layer {} must have a width and height above zero",
+                    i + 1
+                )));
+            }
+            if !(layer.speed.is_finite() && layer.speed >= 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "background layer {} must have a speed of at least zero",
+                    i + 1
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Distance the pipes have scrolled, which the strips follow at a fraction of.
+#[derive(Default)]
+struct BackgroundScroll(f32);
+
+/// A row of tiles repeated across the window, scrolling along with the pipes.
+#[derive(Component)]
+pub struct TiledStrip {
+    /// Fraction of the pipe speed the strip scrolls at, from 0 for still to 1 for the pipes' pace.
+    pub speed: f32,
+    pub tile_width: f32,
+}
+
+#[derive(Component)]
+struct ParallaxLayer;
+
+/// Spawns enough tiles of `size` under a [`TiledStrip`] to cover `visible_width` while it scrolls.
+pub fn spawn_tiles(
+    parent: &mut ChildBuilder,
+    texture: &Handle<Image>,
+    size: Vec2,
+    color: Color,
+    visible_width: f32,
+) {
+    // One spare tile, so the window stays covered as the tiles scroll by up to a tile width
+    let count = (visible_width / size.x).ceil() as usize + 1;
+    for tile in 0..count {
+        parent.spawn_bundle(SpriteBundle {
+            texture: texture.clone(),
+            sprite: Sprite {
+                color,
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(
+                (size.x - visible_width) / 2.0 + tile as f32 * size.x,
+                0.0,
+                0.0,
+            ),
+            ..Default::default()
+        });
+    }
+}
+
+/// Loads the layers of the theme's background, again whenever the theme changes.
+fn load_background(theme: Res<Theme>, mut background: ResMut<BackgroundConfig>) {
+    if theme.is_changed() {
+        let loaded = BackgroundConfig::load_or_default(&theme.world.background);
+        if *background != loaded {
+            *background = loaded;
+        }
+    }
+}
+
+/// Spawns the layers, and again with enough tiles to fill the window when it's resized.
+fn spawn_layers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    background: Res<BackgroundConfig>,
+    config: Res<GameConfig>,
+    playfield: Res<Playfield>,
+    layers: Query<Entity, With<ParallaxLayer>>,
+) {
+    if !background.is_changed() && !playfield.is_changed() && !config.is_changed() {
+        return;
+    }
+
+    for entity in layers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let visible_width = playfield.visible.x.max(config.window_width);
+    for (index, layer) in background.layers.iter().enumerate() {
+        let texture = asset_server.load(layer.image.as_str());
+        let (red, green, blue) = layer.tint;
+
+        commands
+            .spawn_bundle((
+                Transform::from_xyz(0.0, layer.y, BACKGROUND_Z + index as f32),
+                GlobalTransform::default(),
+            ))
+            .insert(ParallaxLayer)
+            .insert(TiledStrip {
+                speed: layer.speed,
+                tile_width: layer.width,
+            })
+            .with_children(|parent| {
+                spawn_tiles(
+                    parent,
+                    &texture,
+                    Vec2::new(layer.width, layer.height),
+                    Color::rgb(red, green, blue),
+                    visible_width,
+                );
+            });
+    }
+}
+
+/// Scrolls the strips along with the pipes while playing, and keeps them in view of the camera.
+fn scroll_strips(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    game_state: Res<GameState>,
+    mut scroll: ResMut<BackgroundScroll>,
+    camera: Query<&Transform, With<MainCamera>>,
+    mut strips: Query<(&TiledStrip, &mut Transform), Without<MainCamera>>,
+) {
+    if matches!(*game_state, GameState::Playing) {
+        scroll.0 += config.pipe_speed * difficulty.speed * timestep.delta_seconds(&time);
+    }
+
+    // The editor moves the camera rather than the pipes, which the strips follow too
+    let camera_x = camera
+        .get_single()
+        .map(|camera| camera.translation.x)
+        .unwrap_or_default();
+    for (strip, mut transform) in strips.iter_mut() {
+        let offset = (scroll.0 + camera_x) * strip.speed;
+        // Wrapping by whole tiles keeps the tiles in place, so it can't be seen
+        transform.translation.x = camera_x - offset.rem_euclid(strip.tile_width);
+    }
+}
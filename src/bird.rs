@@ -8,11 +8,20 @@ use bevy_easings::{
 use heron::prelude::*;
 
 use crate::{
+    config::GameConfig,
+    controls::{ActionInput, InputAction},
     game_state::{run_if_playing, GameStartedEvent, GameState, GameStateLabel},
+    level::LevelCompleteEvent,
+    simulation::Timestep,
+    theme::{BirdSkin, Theme},
     DiedEvent, FlapEvent, GameResetEvent, Layer,
 };
 
-const JUMP_FORCE: f32 = 300.0;
+/// Frames of the wing cycle in the bird's sprite sheet, starting from the resting pose.
+const WING_CYCLE: [usize; 4] = [1, 0, 1, 2];
+
+/// Frame of the bird's sprite sheet shown once it has died.
+const DEAD_FRAME: usize = 3;
 
 pub struct BirdPlugin;
 
@@ -27,7 +36,9 @@ impl Plugin for BirdPlugin {
             .add_system(custom_ease_system::<Rotation>.with_run_criteria(run_if_playing))
             .add_system(sync_rotation)
             .add_system(handle_bird_tilt_up.with_run_criteria(run_if_playing))
-            .add_system(handle_bird_tilt_down.with_run_criteria(run_if_playing));
+            .add_system(handle_bird_tilt_down.with_run_criteria(run_if_playing))
+            .add_system(animate_wings)
+            .add_system(apply_bird_skin);
     }
 }
 
@@ -35,41 +46,116 @@ impl Plugin for BirdPlugin {
 struct GameStartedStage;
 
 #[derive(Component)]
-struct Bird;
-
-fn spawn_bird(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands
-        .spawn_bundle(SpriteBundle {
-            texture: asset_server.load("sprites/bird.png"),
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(32.0, 24.0)),
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .insert(CollisionShape::Cuboid {
-            half_extends: Vec3::new(8.0, 5.0, 0.0),
-            border_radius: Some(6.0),
-        })
+pub struct Bird;
+
+#[derive(Component)]
+struct BirdSprite;
+
+/// Position in the bird's wing animation.
+#[derive(Component, Default)]
+struct WingAnimation {
+    /// Index into [`WING_CYCLE`].
+    step: usize,
+    timer: f32,
+    /// Whether a flap's wing-beat is playing.
+    beating: bool,
+    dead: bool,
+}
+
+fn spawn_bird(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    texture_atlases: Option<ResMut<Assets<TextureAtlas>>>,
+    theme: Res<Theme>,
+) {
+    let mut bird = commands.spawn_bundle((Transform::default(), GlobalTransform::default()));
+    bird.insert(bird_shape(&theme.bird))
         .insert(Velocity::default())
+        .insert(RotationConstraints::lock())
         .insert(CollisionLayers::all::<Layer>().with_group(Layer::Player))
         .insert(Rotation(Quat::default()))
+        .insert(WingAnimation::default())
         .insert(Bird);
+
+    // The tilt is applied to the sprite only, so it never affects the physics body.
+    // Headless apps have no asset server, so the bird is simulated without a sprite.
+    if let (Some(asset_server), Some(mut texture_atlases)) = (asset_server, texture_atlases) {
+        let texture_atlas = bird_atlas(&asset_server, &mut texture_atlases, &theme.bird);
+        bird.with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas,
+                    sprite: TextureAtlasSprite {
+                        index: WING_CYCLE[0],
+                        custom_size: Some(Vec2::new(theme.bird.width, theme.bird.height)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(BirdSprite);
+        });
+    }
+}
+
+fn bird_shape(skin: &BirdSkin) -> CollisionShape {
+    CollisionShape::Cuboid {
+        half_extends: Vec3::new(skin.collider_half_width, skin.collider_half_height, 0.0),
+        border_radius: Some(skin.collider_radius),
+    }
+}
+
+/// Splits the skin's sprite sheet into its wings up, resting, wings down and dead frames.
+fn bird_atlas(
+    asset_server: &AssetServer,
+    texture_atlases: &mut Assets<TextureAtlas>,
+    skin: &BirdSkin,
+) -> Handle<TextureAtlas> {
+    texture_atlases.add(TextureAtlas::from_grid(
+        asset_server.load(skin.sprite_sheet.as_str()),
+        Vec2::new(skin.frame_width, skin.frame_height),
+        4,
+        1,
+    ))
+}
+
+/// Switches the bird to a newly chosen skin.
+fn apply_bird_skin(
+    asset_server: Option<Res<AssetServer>>,
+    texture_atlases: Option<ResMut<Assets<TextureAtlas>>>,
+    theme: Res<Theme>,
+    mut birds: Query<&mut CollisionShape, With<Bird>>,
+    mut sprites: Query<(&mut Handle<TextureAtlas>, &mut TextureAtlasSprite), With<BirdSprite>>,
+) {
+    // The bird is spawned with the theme it starts with
+    if !theme.is_changed() || theme.is_added() {
+        return;
+    }
+
+    for mut shape in birds.iter_mut() {
+        *shape = bird_shape(&theme.bird);
+    }
+    if let (Some(asset_server), Some(mut texture_atlases)) = (asset_server, texture_atlases) {
+        for (mut texture_atlas, mut sprite) in sprites.iter_mut() {
+            *texture_atlas = bird_atlas(&asset_server, &mut texture_atlases, &theme.bird);
+            sprite.custom_size = Some(Vec2::new(theme.bird.width, theme.bird.height));
+        }
+    }
 }
 
 fn bird_input(
-    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<ActionInput>,
+    config: Res<GameConfig>,
     mut bird: Query<&mut Velocity, With<Bird>>,
     mut flap_events: EventWriter<FlapEvent>,
     game_state: Res<GameState>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) && matches!(*game_state, GameState::Playing) {
+    if action_input.just_pressed(InputAction::Flap) && matches!(*game_state, GameState::Playing) {
         let mut velocity = bird.single_mut();
 
         if velocity.linear.y < 0.0 {
             velocity.linear.y = 0.0;
         }
-        velocity.linear.y += JUMP_FORCE;
+        velocity.linear.y += config.jump_force;
 
         flap_events.send(FlapEvent);
     }
@@ -86,11 +172,17 @@ fn handle_game_started(
 }
 
 fn handle_game_reset(
+    mut commands: Commands,
     mut game_reset_events: EventReader<GameResetEvent>,
-    mut bird: Query<(&mut Transform, &mut Velocity, &mut Rotation), With<Bird>>,
+    mut bird: Query<(Entity, &mut Transform, &mut Velocity, &mut Rotation), With<Bird>>,
 ) {
     if game_reset_events.iter().next().is_some() {
-        let (mut transform, mut velocity, mut rotation) = bird.single_mut();
+        let (entity, mut transform, mut velocity, mut rotation) = bird.single_mut();
+        // Runs can be restarted mid-flight, before the bird died and stopped being simulated
+        commands
+            .entity(entity)
+            .remove::<RigidBody>()
+            .remove::<EasingComponent<Rotation>>();
         transform.translation.x = 0.0;
         transform.translation.y = 0.0;
         velocity.linear = Vec3::default();
@@ -99,12 +191,16 @@ fn handle_game_reset(
     }
 }
 
+/// Stops simulating the bird once the run is over, either by dying or finishing a level.
 fn handle_died(
     mut commands: Commands,
     mut died_events: EventReader<DiedEvent>,
+    mut level_complete_events: EventReader<LevelCompleteEvent>,
     mut bird: Query<Entity, With<Bird>>,
 ) {
-    if died_events.iter().next().is_some() {
+    let died = died_events.iter().next().is_some();
+    let level_complete = level_complete_events.iter().next().is_some();
+    if died || level_complete {
         let entity = bird.single_mut();
         commands
             .entity(entity)
@@ -113,6 +209,74 @@ fn handle_died(
     }
 }
 
+/// Flaps the wings gently while idle, beats them after each flap for as long as the bird tilts
+/// upwards, and rests them while it tilts down. Shows the dead frame from dying until a reset.
+fn animate_wings(
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    config: Res<GameConfig>,
+    game_state: Res<GameState>,
+    mut flap_events: EventReader<FlapEvent>,
+    mut died_events: EventReader<DiedEvent>,
+    mut game_reset_events: EventReader<GameResetEvent>,
+    mut birds: Query<(&mut WingAnimation, Option<&TiltUp>, Option<&Children>), With<Bird>>,
+    mut sprites: Query<&mut TextureAtlasSprite, With<BirdSprite>>,
+) {
+    let flapped = flap_events.iter().next().is_some();
+    let died = died_events.iter().next().is_some();
+    let reset = game_reset_events.iter().next().is_some();
+
+    for (mut animation, tilt_up, children) in birds.iter_mut() {
+        if reset {
+            *animation = WingAnimation::default();
+        }
+        if died {
+            animation.dead = true;
+        }
+        if flapped {
+            animation.beating = true;
+            animation.step = 0;
+            animation.timer = 0.0;
+        }
+
+        let frame_time = match *game_state {
+            // Frozen mid-beat while paused
+            GameState::Paused => None,
+            GameState::Playing if animation.beating => Some(config.bird_animation.flap_frame_time),
+            GameState::Playing => None,
+            _ => Some(config.bird_animation.idle_frame_time),
+        };
+        if let Some(frame_time) = frame_time {
+            animation.timer += timestep.delta_seconds(&time);
+            while animation.timer >= frame_time {
+                animation.timer -= frame_time;
+                animation.step = (animation.step + 1) % WING_CYCLE.len();
+                // Each beat finishes its cycle, then the wings rest once the bird tilts down
+                if animation.step == 0 && tilt_up.is_none() {
+                    animation.beating = false;
+                }
+            }
+        }
+        if matches!(*game_state, GameState::Playing) && !animation.beating {
+            animation.step = 0;
+            animation.timer = 0.0;
+        }
+
+        let index = if animation.dead {
+            DEAD_FRAME
+        } else {
+            WING_CYCLE[animation.step]
+        };
+        if let Some(children) = children {
+            for child in children.iter() {
+                if let Ok(mut sprite) = sprites.get_mut(*child) {
+                    sprite.index = index;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Component, Clone, Copy, Default)]
 struct Rotation(Quat);
 
@@ -124,9 +288,16 @@ impl Lerp for Rotation {
     }
 }
 
-fn sync_rotation(mut query: Query<(&mut Transform, &Rotation)>) {
-    for (mut transform, rotation) in query.iter_mut() {
-        transform.rotation = rotation.0;
+fn sync_rotation(
+    birds: Query<(&Rotation, &Children), With<Bird>>,
+    mut sprites: Query<&mut Transform, With<BirdSprite>>,
+) {
+    for (rotation, children) in birds.iter() {
+        for child in children.iter() {
+            if let Ok(mut transform) = sprites.get_mut(*child) {
+                transform.rotation = rotation.0;
+            }
+        }
     }
 }
 
@@ -1,13 +1,156 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::WindowMode};
+
+use crate::config::GameConfig;
+
+/// Height of the letterbox bars, which only needs to cover any window shape.
+const LETTERBOX_SIZE: f32 = 100_000.0;
+
+/// Distance the camera sees behind the playfield at a depth of 0, for the background.
+const BACKGROUND_DEPTH: f32 = 1000.0;
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup);
+        app.init_resource::<Playfield>()
+            .add_startup_system(setup)
+            .add_system(fit_playfield.label(PlayfieldLabel))
+            .add_system(scale_ui_text.after(PlayfieldLabel))
+            .add_system(toggle_fullscreen);
     }
 }
 
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct PlayfieldLabel;
+
+/// The camera showing the playfield, as opposed to the UI camera.
+#[derive(Component)]
+pub struct MainCamera;
+
+/// How the fixed-size logical playfield from the [`GameConfig`] is fitted into the window.
+///
+/// The whole playfield is always visible. Wider windows show more of the course to the sides,
+/// and taller windows are letterboxed above and below.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Playfield {
+    /// Window pixels per logical unit.
+    pub scale: f32,
+    /// Size of the visible area in logical units.
+    pub visible: Vec2,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Playfield {
+            scale: 1.0,
+            visible: Vec2::ZERO,
+        }
+    }
+}
+
+/// Text whose font size scales with the [`Playfield`], given in logical units.
+#[derive(Component)]
+pub struct ScaledText {
+    pub font_size: f32,
+}
+
+#[derive(Component)]
+struct Letterbox {
+    /// 1 for the bar above the playfield, -1 for the one below.
+    side: f32,
+}
+
 fn setup(mut commands: Commands) {
-    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+    let mut camera = OrthographicCameraBundle::new_2d();
+    // The 2D camera only sees from a depth of 0 forwards by default
+    camera.orthographic_projection.far += BACKGROUND_DEPTH;
+    commands
+        .spawn_bundle(camera)
+        .insert(MainCamera)
+        .with_children(|parent| {
+            // Children of the camera, so they stay in place when it scrolls
+            for side in [1.0, -1.0] {
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::BLACK,
+                            custom_size: Some(Vec2::splat(LETTERBOX_SIZE)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert(Letterbox { side });
+            }
+        });
+}
+
+fn fit_playfield(
+    windows: Res<Windows>,
+    config: Res<GameConfig>,
+    mut playfield: ResMut<Playfield>,
+    mut cameras: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut letterboxes: Query<(&Letterbox, &mut Transform)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) if window.width() > 0.0 && window.height() > 0.0 => window,
+        // Minimised windows have no size to fit into
+        _ => return,
+    };
+
+    let window_size = Vec2::new(window.width(), window.height());
+    let scale = (window_size.x / config.window_width).min(window_size.y / config.window_height);
+    let fitted = Playfield {
+        scale,
+        visible: window_size / scale,
+    };
+    if *playfield == fitted {
+        return;
+    }
+    *playfield = fitted;
+
+    for mut projection in cameras.iter_mut() {
+        projection.scale = 1.0 / scale;
+    }
+    for (letterbox, mut transform) in letterboxes.iter_mut() {
+        // In front of everything, just ahead of the camera and its near plane
+        transform.translation = Vec3::new(
+            0.0,
+            letterbox.side * (config.window_height + LETTERBOX_SIZE) / 2.0,
+            -1.0,
+        );
+    }
+}
+
+fn scale_ui_text(playfield: Res<Playfield>, mut texts: Query<(&ScaledText, &mut Text)>) {
+    for (scaled, mut text) in texts.iter_mut() {
+        let font_size = scaled.font_size * playfield.scale;
+        // Skip unchanged text, so it's only laid out again when the window is resized
+        if text
+            .sections
+            .iter()
+            .any(|section| section.style.font_size != font_size)
+        {
+            for section in text.sections.iter_mut() {
+                section.style.font_size = font_size;
+            }
+        }
+    }
+}
+
+/// Toggles borderless fullscreen with F11 or Alt+Enter.
+fn toggle_fullscreen(keyboard_input: Res<Input<KeyCode>>, mut windows: ResMut<Windows>) {
+    let alt = keyboard_input.pressed(KeyCode::LAlt) || keyboard_input.pressed(KeyCode::RAlt);
+    let toggle = keyboard_input.just_pressed(KeyCode::F11)
+        || (alt && keyboard_input.just_pressed(KeyCode::Return));
+    if !toggle {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = match window.mode() {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+        window.set_mode(mode);
+    }
 }
@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use heron::prelude::*;
 
-use crate::{game_state::GameState, DiedEvent, IncreaseScoreEvent, Layer};
+use crate::{
+    game_state::GameState, pipes::PipePair, DeathCause, DiedEvent, IncreaseScoreEvent, Layer,
+};
 
 pub struct CollisionsPlugin;
 
@@ -16,18 +18,35 @@ fn check_collisions(
     mut increase_score_events: EventWriter<IncreaseScoreEvent>,
     mut died_events: EventWriter<DiedEvent>,
     game_state: Res<GameState>,
+    parents: Query<&Parent>,
+    pipe_pairs: Query<(), With<PipePair>>,
 ) {
     if matches!(*game_state, GameState::Playing) {
         for event in collision_events.iter() {
             match event {
                 CollisionEvent::Started(c1, c2) => {
-                    if (c1.collision_layers().contains_group(Layer::Player)
-                        && c2.collision_layers().contains_group(Layer::World))
-                        || (c2.collision_layers().contains_group(Layer::Player)
-                            && c1.collision_layers().contains_group(Layer::World))
+                    let obstacle = if c1.collision_layers().contains_group(Layer::Player)
+                        && c2.collision_layers().contains_group(Layer::World)
                     {
-                        died_events.send(DiedEvent);
-                    }
+                        c2
+                    } else if c2.collision_layers().contains_group(Layer::Player)
+                        && c1.collision_layers().contains_group(Layer::World)
+                    {
+                        c1
+                    } else {
+                        continue;
+                    };
+
+                    // Pipes are children of their pair, unlike the ground and ceiling
+                    let hit_pipe = parents
+                        .get(obstacle.rigid_body_entity())
+                        .map_or(false, |parent| pipe_pairs.get(parent.0).is_ok());
+                    let cause = if hit_pipe {
+                        DeathCause::Pipe
+                    } else {
+                        DeathCause::Bounds
+                    };
+                    died_events.send(DiedEvent { cause });
                 }
                 CollisionEvent::Stopped(c1, c2) => {
                     if (c1.collision_layers().contains_group(Layer::Player)
@@ -0,0 +1,543 @@
+use std::{fmt, fs, io, path::Path};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+/// Path of the gameplay config, relative to the assets folder.
+pub const GAME_CONFIG_PATH: &str = "config/game.config.ron";
+
+/// Extension of the gameplay config, kept apart from plain `.ron` so the other RON assets aren't
+/// loaded as a [`GameConfig`].
+pub const GAME_CONFIG_EXTENSION: &str = "config.ron";
+
+/// Gameplay tuning, loaded from [`GAME_CONFIG_PATH`] so it can be changed without recompiling.
+///
+/// Missing fields fall back to their defaults, while unknown fields are rejected to catch typos.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    /// Width of the playfield and window.
+    pub window_width: f32,
+    /// Height of the playfield and window.
+    pub window_height: f32,
+    /// Downward acceleration of the bird.
+    pub gravity: f32,
+    /// Upward velocity added by each flap.
+    pub jump_force: f32,
+    pub pipe_width: f32,
+    /// Height of the gap between the top and bottom pipe.
+    pub pipe_gap: f32,
+    /// Horizontal distance between consecutive pipe pairs.
+    pub pipe_spacing: f32,
+    /// Minimum distance between a gap's centre and the top or bottom of the playfield.
+    pub pipe_padding: f32,
+    /// Horizontal speed of the pipes.
+    pub pipe_speed: f32,
+    /// Distance from the bird to the first pipe pair.
+    pub first_pipe_x: f32,
+    /// How the difficulty ramps up with the score, as keyframes sorted by score.
+    pub difficulty: Vec<DifficultyKeyframe>,
+    pub pipe_variants: PipeVariants,
+    pub bird_animation: BirdAnimation,
+}
+
+/// Difficulty multipliers reached at a score, interpolated linearly between keyframes.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DifficultyKeyframe {
+    pub score: u32,
+    /// Multiplier for `pipe_speed`.
+    pub speed: f32,
+    /// Multiplier for `pipe_gap`.
+    pub gap: f32,
+    /// Fraction of the available height that gaps are randomly spread over, between 0 and 1.
+    pub variance: f32,
+}
+
+/// Relative chances of each kind of pipe pair being spawned, and how the moving kinds move.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipeVariants {
+    pub static_weight: f32,
+    pub oscillating_weight: f32,
+    pub open_close_weight: f32,
+    pub drifting_weight: f32,
+    /// Vertical distance oscillating gaps move either side of their centre.
+    pub oscillation_amplitude: f32,
+    /// Oscillations per second.
+    pub oscillation_frequency: f32,
+    /// Fraction of the gap that opening and closing gaps close by at their narrowest.
+    pub close_fraction: f32,
+    /// Open and close cycles per second.
+    pub open_close_frequency: f32,
+    /// Vertical speed of drifting gaps.
+    pub drift_speed: f32,
+}
+
+impl Default for PipeVariants {
+    fn default() -> Self {
+        PipeVariants {
+            static_weight: 6.0,
+            oscillating_weight: 2.0,
+            open_close_weight: 1.0,
+            drifting_weight: 1.0,
+            oscillation_amplitude: 60.0,
+            oscillation_frequency: 0.5,
+            close_fraction: 0.4,
+            open_close_frequency: 0.4,
+            drift_speed: 40.0,
+        }
+    }
+}
+
+/// Frame timing of the bird's wing animation, in seconds per frame.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BirdAnimation {
+    /// Gentle flapping while waiting for the run to start or after finishing a level.
+    pub idle_frame_time: f32,
+    /// Wing-beat after each flap, kept up for as long as the bird tilts upwards.
+    pub flap_frame_time: f32,
+}
+
+impl Default for BirdAnimation {
+    fn default() -> Self {
+        BirdAnimation {
+            idle_frame_time: 0.15,
+            flap_frame_time: 0.05,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            window_width: 1200.0,
+            window_height: 600.0,
+            gravity: 600.0,
+            jump_force: 300.0,
+            pipe_width: 80.0,
+            pipe_gap: 140.0,
+            pipe_spacing: 256.0,
+            pipe_padding: 160.0,
+            pipe_speed: 100.0,
+            first_pipe_x: 300.0,
+            difficulty: vec![
+                DifficultyKeyframe {
+                    score: 0,
+                    speed: 1.0,
+                    gap: 1.0,
+                    variance: 0.5,
+                },
+                DifficultyKeyframe {
+                    score: 10,
+                    speed: 1.25,
+                    gap: 0.9,
+                    variance: 0.8,
+                },
+                DifficultyKeyframe {
+                    score: 40,
+                    speed: 1.6,
+                    gap: 0.75,
+                    variance: 1.0,
+                },
+            ],
+            pipe_variants: PipeVariants::default(),
+            bird_animation: BirdAnimation::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Loads and validates the config from the assets folder.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(&storage::asset_path(GAME_CONFIG_PATH))
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, ConfigError> {
+        let config: GameConfig = ron::de::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the config, logging any error and falling back to the defaults.
+    pub fn load_or_default() -> Self {
+        GameConfig::load().unwrap_or_else(|err| {
+            warn!("using default game config: {}", err);
+            GameConfig::default()
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let positive = [
+            ("window_width", self.window_width),
+            ("window_height", self.window_height),
+            ("jump_force", self.jump_force),
+            ("pipe_width", self.pipe_width),
+            ("pipe_gap", self.pipe_gap),
+            ("pipe_spacing", self.pipe_spacing),
+            ("pipe_speed", self.pipe_speed),
+        ];
+        for (name, value) in positive {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "{} must be greater than zero, got {}",
+                    name, value
+                )));
+            }
+        }
+
+        let non_negative = [
+            ("gravity", self.gravity),
+            ("pipe_padding", self.pipe_padding),
+            ("first_pipe_x", self.first_pipe_x),
+        ];
+        for (name, value) in non_negative {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "{} must not be negative, got {}",
+                    name, value
+                )));
+            }
+        }
+
+        if self.pipe_padding * 2.0 >= self.window_height {
+            return Err(ConfigError::Invalid(format!(
+                "pipe_padding must be less than half of window_height ({})",
+                self.window_height / 2.0
+            )));
+        }
+        if self.pipe_gap >= self.window_height {
+            return Err(ConfigError::Invalid(format!(
+                "pipe_gap must be less than window_height ({})",
+                self.window_height
+            )));
+        }
+        self.pipe_variants.validate()?;
+        self.bird_animation.validate()?;
+
+        for (i, keyframe) in self.difficulty.iter().enumerate() {
+            if i > 0 && keyframe.score <= self.difficulty[i - 1].score {
+                return Err(ConfigError::Invalid(format!(
+                    "difficulty keyframes must be sorted by score, got {} after {}",
+                    keyframe.score,
+                    self.difficulty[i - 1].score
+                )));
+            }
+            let positive = |value: f32| value.is_finite() && value > 0.0;
+            if !(positive(keyframe.speed) && positive(keyframe.gap)) {
+                return Err(ConfigError::Invalid(format!(
+                    "difficulty speed and gap must be greater than zero at score {}",
+                    keyframe.score
+                )));
+            }
+            if self.pipe_gap * keyframe.gap >= self.window_height {
+                return Err(ConfigError::Invalid(format!(
+                    "difficulty gap at score {} makes the gap taller than window_height",
+                    keyframe.score
+                )));
+            }
+            if !(0.0..=1.0).contains(&keyframe.variance) {
+                return Err(ConfigError::Invalid(format!(
+                    "difficulty variance must be between 0 and 1 at score {}, got {}",
+                    keyframe.score, keyframe.variance
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Height of each pipe sprite and collider.
+    pub fn pipe_height(&self) -> f32 {
+        self.window_height - self.pipe_width / 2.0
+    }
+}
+
+impl PipeVariants {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let non_negative = [
+            ("static_weight", self.static_weight),
+            ("oscillating_weight", self.oscillating_weight),
+            ("open_close_weight", self.open_close_weight),
+            ("drifting_weight", self.drifting_weight),
+            ("oscillation_amplitude", self.oscillation_amplitude),
+            ("oscillation_frequency", self.oscillation_frequency),
+            ("open_close_frequency", self.open_close_frequency),
+            ("drift_speed", self.drift_speed),
+        ];
+        for (name, value) in non_negative {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "pipe_variants.{} must not be negative, got {}",
+                    name, value
+                )));
+            }
+        }
+
+        if self.weights().iter().sum::<f32>() <= 0.0 {
+            return Err(ConfigError::Invalid(
+                "pipe_variants must have at least one weight above zero".to_string(),
+            ));
+        }
+        if !(0.0..1.0).contains(&self.close_fraction) {
+            return Err(ConfigError::Invalid(format!(
+                "pipe_variants.close_fraction must be at least 0 and less than 1, got {}",
+                self.close_fraction
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Weights of the static, oscillating, opening and closing, and drifting variants.
+    pub fn weights(&self) -> [f32; 4] {
+        [
+            self.static_weight,
+            self.oscillating_weight,
+            self.open_close_weight,
+            self.drifting_weight,
+        ]
+    }
+}
+
+impl BirdAnimation {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let positive = [
+            ("idle_frame_time", self.idle_frame_time),
+            ("flap_frame_time", self.flap_frame_time),
+        ];
+        for (name, value) in positive {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "bird_animation.{} must be greater than zero, got {}",
+                    name, value
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Format(ron::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "{}", err),
+            ConfigError::Format(err) => write!(f, "invalid config file: {}", err),
+            ConfigError::Invalid(reason) => write!(f, "invalid config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<ron::Error> for ConfigError {
+    fn from(err: ron::Error) -> Self {
+        ConfigError::Format(err)
+    }
+}
+
+/// Applies edits to the gameplay config asset while the game is running.
+///
+/// Invalid edits are logged and shown in an overlay, and the last valid config is kept.
+pub struct ConfigReloadPlugin;
+
+impl Plugin for ConfigReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<GameConfigAsset>()
+            .init_asset_loader::<GameConfigLoader>()
+            .init_resource::<ConfigStatus>()
+            .add_startup_system(watch_config)
+            .add_system(handle_config_events)
+            .add_system(update_config_error_text);
+    }
+}
+
+/// A parsed and validated config file, or the reason it was rejected.
+#[derive(TypeUuid)]
+#[uuid = "6c6a3b4e-1f2d-4c8e-9b57-2d8f0a4e7c31"]
+pub struct GameConfigAsset(pub Result<GameConfig, String>);
+
+#[derive(Default)]
+struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            // Invalid files still load, so the error can be reported instead of only logged
+            let config = std::str::from_utf8(bytes)
+                .map_err(|err| err.to_string())
+                .and_then(|contents| GameConfig::from_ron(contents).map_err(|err| err.to_string()));
+            load_context.set_default_asset(LoadedAsset::new(GameConfigAsset(config)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[GAME_CONFIG_EXTENSION]
+    }
+}
+
+/// The error from the last rejected edit to the config file, if it hasn't been fixed since.
+#[derive(Default)]
+struct ConfigStatus {
+    error: Option<String>,
+}
+
+/// Keeps the config asset loaded and watched for changes.
+struct GameConfigHandle(#[allow(dead_code)] Handle<GameConfigAsset>);
+
+#[derive(Component)]
+struct ConfigErrorText;
+
+fn watch_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    if let Err(err) = asset_server.watch_for_changes() {
+        warn!("config hot-reload disabled: {:?}", err);
+    }
+    commands.insert_resource(GameConfigHandle(asset_server.load(GAME_CONFIG_PATH)));
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(15.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 20.0,
+                    color: Color::ORANGE_RED,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(ConfigErrorText);
+}
+
+fn handle_config_events(
+    mut asset_events: EventReader<AssetEvent<GameConfigAsset>>,
+    assets: Res<Assets<GameConfigAsset>>,
+    mut config: ResMut<GameConfig>,
+    mut status: ResMut<ConfigStatus>,
+) {
+    for event in asset_events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        let asset = match assets.get(handle) {
+            Some(asset) => asset,
+            None => continue,
+        };
+
+        match &asset.0 {
+            Ok(new_config) => {
+                if *config != *new_config {
+                    info!("reloaded game config");
+                    *config = new_config.clone();
+                }
+                status.error = None;
+            }
+            Err(err) => {
+                warn!("ignoring edit to {}: {}", GAME_CONFIG_PATH, err);
+                status.error = Some(err.clone());
+            }
+        }
+    }
+}
+
+fn update_config_error_text(
+    status: Res<ConfigStatus>,
+    mut text: Query<&mut Text, With<ConfigErrorText>>,
+) {
+    if status.is_changed() {
+        for mut text in text.iter_mut() {
+            text.sections[0].value = match &status.error {
+                Some(err) => format!("{}: {}", GAME_CONFIG_PATH, err),
+                None => String::new(),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaps_have_to_fit_in_the_window() {
+        let fits = GameConfig {
+            pipe_gap: 590.0,
+            pipe_padding: 290.0,
+            ..GameConfig::default()
+        };
+        fits.validate().unwrap();
+
+        let too_tall = GameConfig {
+            pipe_gap: 600.0,
+            ..fits.clone()
+        };
+        assert!(too_tall.validate().is_err());
+
+        let too_padded = GameConfig {
+            pipe_padding: 300.0,
+            ..fits
+        };
+        assert!(too_padded.validate().is_err());
+    }
+
+    #[test]
+    fn difficulty_keyframes_have_to_be_sorted_by_score() {
+        let config = |scores: &[u32]| GameConfig {
+            difficulty: scores
+                .iter()
+                .map(|&score| DifficultyKeyframe {
+                    score,
+                    speed: 1.0,
+                    gap: 1.0,
+                    variance: 0.5,
+                })
+                .collect(),
+            ..GameConfig::default()
+        };
+        config(&[0, 10, 40]).validate().unwrap();
+        assert!(config(&[10, 5]).validate().is_err());
+        assert!(config(&[0, 0]).validate().is_err());
+    }
+}
@@ -0,0 +1,351 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use bevy::{input::InputSystem, prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMap>()
+            .init_resource::<ActionInput>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                read_action_input.label(ControlsLabel).after(InputSystem),
+            )
+            .add_system_to_stage(CoreStage::Last, clear_action_input);
+    }
+}
+
+/// Systems that fill in [`ActionInput`] for the current frame.
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct ControlsLabel;
+
+/// Something the player can do, independent of the device used to do it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    Flap,
+    Pause,
+    Restart,
+    Menu,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 4] = [
+        InputAction::Flap,
+        InputAction::Pause,
+        InputAction::Restart,
+        InputAction::Menu,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAction::Flap => "Flap",
+            InputAction::Pause => "Pause",
+            InputAction::Restart => "Restart",
+            InputAction::Menu => "Menu",
+        }
+    }
+
+    /// Whether the two actions can be triggered in the same game state, so must not share a
+    /// binding. Pausing only happens during a run and the menu action only outside of one.
+    pub fn conflicts_with(&self, other: InputAction) -> bool {
+        !matches!(
+            (self, other),
+            (InputAction::Pause, InputAction::Menu) | (InputAction::Menu, InputAction::Pause)
+        )
+    }
+}
+
+/// A key or button that can be bound to an [`InputAction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+
+    fn same_device(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// The bindings of every [`InputAction`]. Touchscreen taps always flap.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputMap {
+    bindings: HashMap<InputAction, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let bindings = [
+            (
+                InputAction::Flap,
+                vec![
+                    Binding::Key(KeyCode::Space),
+                    Binding::Mouse(MouseButton::Left),
+                    Binding::Gamepad(GamepadButtonType::South),
+                    Binding::Gamepad(GamepadButtonType::East),
+                    Binding::Gamepad(GamepadButtonType::North),
+                    Binding::Gamepad(GamepadButtonType::West),
+                ],
+            ),
+            (
+                InputAction::Pause,
+                vec![
+                    Binding::Key(KeyCode::Escape),
+                    Binding::Key(KeyCode::P),
+                    Binding::Gamepad(GamepadButtonType::Start),
+                ],
+            ),
+            (InputAction::Restart, vec![Binding::Key(KeyCode::R)]),
+            (
+                InputAction::Menu,
+                vec![
+                    Binding::Key(KeyCode::Escape),
+                    Binding::Gamepad(GamepadButtonType::Select),
+                ],
+            ),
+        ];
+
+        InputMap {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl InputMap {
+    /// Loads the input map from the settings file, falling back to the defaults if it's
+    /// missing, corrupt or contains conflicting bindings.
+    pub fn load() -> Self {
+        let input_map: InputMap = settings_path()
+            .as_deref()
+            .map(storage::load_or_default)
+            .unwrap_or_default();
+
+        for action in InputAction::ALL {
+            for binding in input_map.bindings(action) {
+                if let Some(other) = input_map.conflict(action, *binding) {
+                    warn!(
+                        "{} is bound to both {} and {}, using default bindings",
+                        binding.name(),
+                        action.name(),
+                        other.name()
+                    );
+                    return InputMap::default();
+                }
+            }
+        }
+
+        input_map
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = settings_path() {
+            if let Err(err) = storage::save(&path, self) {
+                error!("failed to save input settings: {}", err);
+            }
+        }
+    }
+
+    pub fn bindings(&self, action: InputAction) -> &[Binding] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Describes the bindings of `action` for display, such as "Space / Mouse Left".
+    pub fn describe(&self, action: InputAction) -> String {
+        let names: Vec<_> = self.bindings(action).iter().map(Binding::name).collect();
+        if names.is_empty() {
+            "Unbound".to_string()
+        } else {
+            names.join(" / ")
+        }
+    }
+
+    /// Names the first binding of `action`, for use in prompts.
+    pub fn describe_primary(&self, action: InputAction) -> String {
+        self.bindings(action)
+            .first()
+            .map_or_else(|| "Unbound".to_string(), Binding::name)
+    }
+
+    /// Returns the action `binding` is already bound to that would conflict with `action`.
+    pub fn conflict(&self, action: InputAction, binding: Binding) -> Option<InputAction> {
+        InputAction::ALL.iter().copied().find(|other| {
+            *other != action
+                && action.conflicts_with(*other)
+                && self.bindings(*other).contains(&binding)
+        })
+    }
+
+    /// Binds `binding` to `action` in place of the action's first binding from the same device,
+    /// keeping the rest.
+    ///
+    /// Fails with the conflicting action if `binding` is already in use.
+    pub fn rebind(&mut self, action: InputAction, binding: Binding) -> Result<(), InputAction> {
+        if let Some(other) = self.conflict(action, binding) {
+            return Err(other);
+        }
+
+        let bindings = self.bindings.entry(action).or_default();
+        if bindings.contains(&binding) {
+            return Ok(());
+        }
+        match bindings
+            .iter_mut()
+            .find(|existing| existing.same_device(&binding))
+        {
+            Some(existing) => *existing = binding,
+            None => bindings.push(binding),
+        }
+        Ok(())
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    storage::config_path("input.ron")
+}
+
+/// The actions triggered this frame, decoupled from the devices that produced them.
+///
+/// Presses made before the frame runs (for example by [`crate::env::FlappyEnv`]) are kept
+/// and combined with device input; all presses are cleared at the end of every frame.
+#[derive(Default)]
+pub struct ActionInput {
+    just_pressed: HashSet<InputAction>,
+}
+
+impl ActionInput {
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn press(&mut self, action: InputAction) {
+        self.just_pressed.insert(action);
+    }
+
+    pub fn release(&mut self, action: InputAction) {
+        self.just_pressed.remove(&action);
+    }
+
+    pub fn clear(&mut self) {
+        self.just_pressed.clear();
+    }
+}
+
+fn read_action_input(
+    input_map: Res<InputMap>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut action_input: ResMut<ActionInput>,
+) {
+    for action in InputAction::ALL {
+        let pressed = input_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => keyboard_input.just_pressed(*key),
+                Binding::Mouse(button) => mouse_input.just_pressed(*button),
+                Binding::Gamepad(button) => gamepads
+                    .iter()
+                    .any(|gamepad| gamepad_input.just_pressed(GamepadButton(*gamepad, *button))),
+            });
+
+        if pressed {
+            action_input.press(action);
+        }
+    }
+
+    if touches.iter_just_pressed().next().is_some() {
+        action_input.press(InputAction::Flap);
+    }
+}
+
+fn clear_action_input(mut action_input: ResMut<ActionInput>) {
+    action_input.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_replaces_one_binding_and_keeps_the_rest() {
+        let mut input_map = InputMap::default();
+        input_map
+            .rebind(
+                InputAction::Flap,
+                Binding::Gamepad(GamepadButtonType::RightTrigger),
+            )
+            .unwrap();
+
+        assert_eq!(
+            input_map.bindings(InputAction::Flap),
+            [
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButtonType::RightTrigger),
+                Binding::Gamepad(GamepadButtonType::East),
+                Binding::Gamepad(GamepadButtonType::North),
+                Binding::Gamepad(GamepadButtonType::West),
+            ]
+        );
+    }
+
+    #[test]
+    fn rebinding_a_new_device_adds_a_binding() {
+        let mut input_map = InputMap::default();
+        input_map
+            .rebind(InputAction::Restart, Binding::Mouse(MouseButton::Right))
+            .unwrap();
+
+        assert_eq!(
+            input_map.bindings(InputAction::Restart),
+            [Binding::Key(KeyCode::R), Binding::Mouse(MouseButton::Right)]
+        );
+    }
+
+    #[test]
+    fn rebinding_to_a_current_binding_changes_nothing() {
+        let mut input_map = InputMap::default();
+        input_map
+            .rebind(InputAction::Pause, Binding::Key(KeyCode::P))
+            .unwrap();
+
+        assert_eq!(
+            input_map.bindings(InputAction::Pause),
+            InputMap::default().bindings(InputAction::Pause)
+        );
+    }
+
+    #[test]
+    fn conflicting_bindings_are_refused() {
+        let mut input_map = InputMap::default();
+
+        assert_eq!(
+            input_map.rebind(InputAction::Flap, Binding::Key(KeyCode::R)),
+            Err(InputAction::Restart)
+        );
+        assert_eq!(
+            input_map.bindings(InputAction::Flap),
+            InputMap::default().bindings(InputAction::Flap)
+        );
+    }
+}
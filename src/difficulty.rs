@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::{DifficultyKeyframe, GameConfig},
+    level::CurrentLevel,
+    score::Score,
+};
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>()
+            .add_system(update_difficulty.label(DifficultyLabel));
+    }
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct DifficultyLabel;
+
+/// Current difficulty multipliers, following the config's curve as the score grows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Difficulty {
+    pub speed: f32,
+    pub gap: f32,
+    pub variance: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty {
+            speed: 1.0,
+            gap: 1.0,
+            variance: 1.0,
+        }
+    }
+}
+
+impl Difficulty {
+    /// Interpolates `curve` at `score`, holding the first and last keyframes beyond its ends.
+    pub fn at(curve: &[DifficultyKeyframe], score: u32) -> Self {
+        let next = curve.iter().position(|keyframe| keyframe.score > score);
+        let (from, to) = match next {
+            None => match curve.last() {
+                Some(last) => (last, last),
+                None => return Difficulty::default(),
+            },
+            Some(0) => (&curve[0], &curve[0]),
+            Some(i) => (&curve[i - 1], &curve[i]),
+        };
+
+        let t = if to.score > from.score {
+            (score - from.score) as f32 / (to.score - from.score) as f32
+        } else {
+            0.0
+        };
+        Difficulty {
+            speed: from.speed + (to.speed - from.speed) * t,
+            gap: from.gap + (to.gap - from.gap) * t,
+            variance: from.variance + (to.variance - from.variance) * t,
+        }
+    }
+}
+
+fn update_difficulty(
+    config: Res<GameConfig>,
+    current_level: Res<CurrentLevel>,
+    score: Query<&Score>,
+    mut difficulty: ResMut<Difficulty>,
+) {
+    if let Some(score) = score.iter().next() {
+        // Levels set their own speed, and every gap is placed by hand
+        let new_difficulty = match &current_level.level {
+            Some(level) => Difficulty {
+                speed: level.speed_at(score.0),
+                ..Difficulty::default()
+            },
+            None => Difficulty::at(&config.difficulty, score.0),
+        };
+        if *difficulty != new_difficulty {
+            *difficulty = new_difficulty;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(score: u32, speed: f32) -> DifficultyKeyframe {
+        DifficultyKeyframe {
+            score,
+            speed,
+            gap: 2.0 - speed,
+            variance: speed / 2.0,
+        }
+    }
+
+    fn curve() -> Vec<DifficultyKeyframe> {
+        vec![keyframe(10, 1.0), keyframe(20, 1.5), keyframe(40, 1.75)]
+    }
+
+    #[test]
+    fn empty_curves_give_the_default() {
+        assert_eq!(Difficulty::at(&[], 5), Difficulty::default());
+    }
+
+    #[test]
+    fn keyframes_are_hit_exactly() {
+        let difficulty = Difficulty::at(&curve(), 20);
+        assert_eq!(
+            difficulty,
+            Difficulty {
+                speed: 1.5,
+                gap: 0.5,
+                variance: 0.75,
+            }
+        );
+    }
+
+    #[test]
+    fn scores_between_keyframes_are_interpolated() {
+        let difficulty = Difficulty::at(&curve(), 15);
+        assert_eq!(
+            difficulty,
+            Difficulty {
+                speed: 1.25,
+                gap: 0.75,
+                variance: 0.625,
+            }
+        );
+        assert_eq!(Difficulty::at(&curve(), 30).speed, 1.625);
+    }
+
+    #[test]
+    fn the_ends_of_the_curve_are_held() {
+        let first = Difficulty::at(&curve(), 10);
+        assert_eq!(Difficulty::at(&curve(), 0), first);
+        assert_eq!(Difficulty::at(&curve(), 9), first);
+
+        let last = Difficulty::at(&curve(), 40);
+        assert_eq!(last.speed, 1.75);
+        assert_eq!(Difficulty::at(&curve(), 41), last);
+        assert_eq!(Difficulty::at(&curve(), u32::MAX), last);
+    }
+
+    #[test]
+    fn single_keyframes_apply_at_every_score() {
+        let curve = [keyframe(5, 1.5)];
+        assert_eq!(Difficulty::at(&curve, 0).speed, 1.5);
+        assert_eq!(Difficulty::at(&curve, 100).speed, 1.5);
+    }
+}
@@ -0,0 +1,673 @@
+use std::{cmp::Ordering, path::PathBuf};
+
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::{
+    camera::{MainCamera, Playfield},
+    config::GameConfig,
+    controls::{ActionInput, InputAction},
+    game_state::{GameState, GameStateLabel},
+    level::{CurrentLevel, Level, LevelPipe},
+    pipes::{gap_offset, PipeKind, RespawnPipesEvent},
+    storage, GameResetEvent,
+};
+
+/// File name of the course edited in the level editor, inside the platform data directory.
+pub const COURSE_FILE: &str = "course.level";
+
+/// How far the timeline scrolls per frame while a scroll key is held, or per mouse wheel step.
+const SCROLL_SPEED: f32 = 12.0;
+/// How much the gap of the selected pipe pair grows or shrinks per key press.
+const GAP_STEP: f32 = 10.0;
+const MIN_GAP: f32 = 40.0;
+
+/// Lets designers build courses with the mouse, play-test them and save them as levels.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        let path = storage::data_path(COURSE_FILE);
+        let level = path
+            .as_deref()
+            .and_then(|path| Level::load(path).ok())
+            .unwrap_or_else(new_course);
+
+        app.insert_resource(EditorCourse {
+            level,
+            path,
+            selected: None,
+            drag_offset: None,
+            scroll: 0.0,
+            testing: false,
+            dirty: false,
+            message: None,
+        })
+        .add_startup_system(spawn_selection_marker)
+        .add_system(enter_editor)
+        .add_system(scroll_timeline.label(EditorLabel))
+        .add_system(edit_with_mouse.label(EditorLabel))
+        .add_system(edit_with_keyboard.label(EditorLabel))
+        .add_system(apply_course.after(EditorLabel))
+        .add_system(update_selection_marker.after(EditorLabel))
+        // After the editing systems, so the Menu press that leaves the run isn't also seen by
+        // them as leaving the editor
+        .add_system(return_to_editor.after(EditorLabel).before(GameStateLabel))
+        .add_system(show_editor_text)
+        .add_system(update_editor_text.after(EditorLabel))
+        .add_system(hide_editor_text);
+    }
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+struct EditorLabel;
+
+/// The course being edited, which is shown through the [`CurrentLevel`] while in the editor.
+pub struct EditorCourse {
+    pub level: Level,
+    /// Where the course is saved and loaded, if there's a data directory.
+    pub path: Option<PathBuf>,
+    /// Index of the selected pipe pair.
+    selected: Option<usize>,
+    /// Offset from the selected pair's gap to the cursor while it's being dragged.
+    drag_offset: Option<Vec2>,
+    /// Horizontal position of the camera over the timeline.
+    scroll: f32,
+    /// Whether the course is being play-tested, so leaving the run returns to the editor.
+    testing: bool,
+    /// Set when the course changed and its pipes need laying out again.
+    dirty: bool,
+    message: Option<String>,
+}
+
+fn new_course() -> Level {
+    Level {
+        name: "Custom Course".to_string(),
+        pipes: vec![LevelPipe {
+            spacing: 0.0,
+            y: 0.0,
+            gap: None,
+            kind: PipeKind::Static,
+            speed: None,
+        }],
+        finish: 200.0,
+    }
+}
+
+impl EditorCourse {
+    /// Horizontal positions of the pipe pairs, as spawned by the level.
+    fn positions(&self, config: &GameConfig) -> Vec<f32> {
+        self.level
+            .pipes
+            .iter()
+            .scan(config.first_pipe_x, |x, pipe| {
+                *x += pipe.spacing;
+                Some(*x)
+            })
+            .collect()
+    }
+
+    /// Moves the pipe pairs to `positions`, keeping them in order and the selection on the same
+    /// pair.
+    fn set_positions(&mut self, config: &GameConfig, positions: Vec<f32>) {
+        let mut pipes: Vec<(f32, LevelPipe, bool)> = positions
+            .into_iter()
+            .zip(self.level.pipes.iter().copied())
+            .enumerate()
+            .map(|(i, (x, pipe))| (x.max(config.first_pipe_x), pipe, self.selected == Some(i)))
+            .collect();
+        pipes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        self.selected = pipes.iter().position(|(_, _, selected)| *selected);
+        let mut previous = config.first_pipe_x;
+        self.level.pipes = pipes
+            .into_iter()
+            .map(|(x, pipe, _)| {
+                let spacing = x - previous;
+                previous = x;
+                LevelPipe { spacing, ..pipe }
+            })
+            .collect();
+        self.dirty = true;
+    }
+
+    /// Index of the pipe pair whose column contains `x`.
+    fn pipe_at(&self, config: &GameConfig, x: f32) -> Option<usize> {
+        self.positions(config)
+            .iter()
+            .position(|pipe_x| (pipe_x - x).abs() <= config.pipe_width / 2.0)
+    }
+
+    /// The course from the pair at `start` onwards, with that pair first.
+    fn from_pipe(&self, start: usize) -> Level {
+        let mut level = self.level.clone();
+        level
+            .pipes
+            .drain(..start.min(level.pipes.len().saturating_sub(1)));
+        if let Some(first) = level.pipes.first_mut() {
+            first.spacing = 0.0;
+        }
+        level
+    }
+}
+
+/// Position between -1 and 1 of a gap whose centre is at height `y`, the inverse of [`gap_offset`].
+fn gap_position(config: &GameConfig, y: f32) -> f32 {
+    (y / gap_offset(config, 1.0)).clamp(-1.0, 1.0)
+}
+
+fn next_kind(kind: PipeKind) -> PipeKind {
+    match kind {
+        PipeKind::Static => PipeKind::Oscillating,
+        PipeKind::Oscillating => PipeKind::OpenClose,
+        PipeKind::OpenClose => PipeKind::Drifting,
+        PipeKind::Drifting => PipeKind::Static,
+    }
+}
+
+fn enter_editor(game_state: Res<GameState>, mut course: ResMut<EditorCourse>) {
+    if game_state.is_changed() && matches!(*game_state, GameState::Editor) {
+        course.testing = false;
+        course.drag_offset = None;
+        course.dirty = true;
+    }
+}
+
+/// Shows the edited course through the level pipes, so it looks exactly like it plays.
+///
+/// Entering the editor resets the run left behind by a play-test, but edits only lay the pipes
+/// out again, as they can happen every frame while a pair is dragged.
+fn apply_course(
+    game_state: Res<GameState>,
+    mut course: ResMut<EditorCourse>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+    mut respawn_pipes_events: EventWriter<RespawnPipesEvent>,
+) {
+    // The camera only scrolls along the timeline while editing
+    let editing = matches!(*game_state, GameState::Editor);
+    let scroll = if editing { course.scroll } else { 0.0 };
+    for mut transform in camera.iter_mut() {
+        if transform.translation.x != scroll {
+            transform.translation.x = scroll;
+        }
+    }
+    if !editing {
+        return;
+    }
+
+    let entered = game_state.is_changed();
+    if course.dirty || entered {
+        course.dirty = false;
+        *current_level = CurrentLevel {
+            level: Some(course.level.clone()),
+            campaign_index: None,
+        };
+        if entered {
+            game_reset_events.send(GameResetEvent);
+        } else {
+            respawn_pipes_events.send(RespawnPipesEvent);
+        }
+    }
+}
+
+fn scroll_timeline(
+    game_state: Res<GameState>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut course: ResMut<EditorCourse>,
+) {
+    let wheel: f32 = mouse_wheel_events.iter().map(|event| event.y).sum();
+    if !matches!(*game_state, GameState::Editor) {
+        return;
+    }
+
+    let mut scroll = -wheel * SCROLL_SPEED * 4.0;
+    let pressed = |keys: &[KeyCode]| keys.iter().any(|key| keyboard_input.pressed(*key));
+    if pressed(&[KeyCode::Left, KeyCode::A]) {
+        scroll -= SCROLL_SPEED;
+    }
+    if pressed(&[KeyCode::Right, KeyCode::D]) {
+        scroll += SCROLL_SPEED;
+    }
+    if scroll != 0.0 {
+        course.scroll = (course.scroll + scroll).max(0.0);
+    }
+}
+
+/// Left click selects and drags a pipe pair, or adds one on empty space. Right click deletes.
+fn edit_with_mouse(
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    windows: Res<Windows>,
+    playfield: Res<Playfield>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut course: ResMut<EditorCourse>,
+) {
+    // Ignore the click that opened the editor from the menu
+    if !matches!(*game_state, GameState::Editor) || game_state.is_changed() {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let cursor = match window.cursor_position() {
+        Some(cursor) => {
+            (cursor - Vec2::new(window.width(), window.height()) / 2.0) / playfield.scale
+                + Vec2::X * course.scroll
+        }
+        None => return,
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let index = match course.pipe_at(&config, cursor.x) {
+            Some(index) => index,
+            None => {
+                let mut positions = course.positions(&config);
+                positions.push(cursor.x);
+                course.level.pipes.push(LevelPipe {
+                    spacing: 0.0,
+                    y: gap_position(&config, cursor.y),
+                    gap: None,
+                    kind: PipeKind::Static,
+                    speed: None,
+                });
+                course.selected = Some(positions.len() - 1);
+                course.set_positions(&config, positions);
+                course.selected.unwrap_or_default()
+            }
+        };
+        let x = course.positions(&config)[index];
+        let y = gap_offset(&config, course.level.pipes[index].y);
+        course.selected = Some(index);
+        course.drag_offset = Some(Vec2::new(x, y) - cursor);
+    }
+
+    if mouse_input.just_released(MouseButton::Left) {
+        course.drag_offset = None;
+    }
+
+    if let (Some(index), Some(offset)) = (course.selected, course.drag_offset) {
+        if mouse_input.pressed(MouseButton::Left) {
+            let target = cursor + offset;
+            let mut positions = course.positions(&config);
+            let y = gap_position(&config, target.y);
+            if positions[index] != target.x || course.level.pipes[index].y != y {
+                positions[index] = target.x;
+                course.level.pipes[index].y = y;
+                course.set_positions(&config, positions);
+            }
+        }
+    }
+
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some(index) = course.pipe_at(&config, cursor.x) {
+            remove_pipe(&mut course, &config, index);
+        }
+    }
+}
+
+fn remove_pipe(course: &mut EditorCourse, config: &GameConfig, index: usize) {
+    // Levels need at least one pipe pair, so there's always something to show and play-test
+    if course.level.pipes.len() <= 1 {
+        course.message = Some("A course needs at least one pipe pair".to_string());
+        return;
+    }
+
+    let mut positions = course.positions(config);
+    positions.remove(index);
+    course.level.pipes.remove(index);
+    course.selected = None;
+    course.drag_offset = None;
+    course.set_positions(config, positions);
+}
+
+/// Adjusts the selected pipe pair, play-tests, and saves or loads the course.
+fn edit_with_keyboard(
+    mut game_state: ResMut<GameState>,
+    config: Res<GameConfig>,
+    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<ActionInput>,
+    mut course: ResMut<EditorCourse>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+) {
+    if !matches!(*game_state, GameState::Editor) {
+        return;
+    }
+
+    if let Some(index) = course.selected {
+        let pipe = &mut course.level.pipes[index];
+        let gap = pipe.gap.unwrap_or(config.pipe_gap);
+        let mut changed = true;
+        if keyboard_input.just_pressed(KeyCode::Up) {
+            pipe.gap = Some((gap + GAP_STEP).min(config.window_height - GAP_STEP));
+        } else if keyboard_input.just_pressed(KeyCode::Down) {
+            pipe.gap = Some((gap - GAP_STEP).max(MIN_GAP));
+        } else if keyboard_input.just_pressed(KeyCode::K) {
+            pipe.kind = next_kind(pipe.kind);
+        } else if keyboard_input.just_pressed(KeyCode::Delete)
+            || keyboard_input.just_pressed(KeyCode::Back)
+        {
+            remove_pipe(&mut course, &config, index);
+        } else {
+            changed = false;
+        }
+        if changed {
+            course.dirty = true;
+        }
+    }
+
+    let control =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+    if control && keyboard_input.just_pressed(KeyCode::S) {
+        course.message = Some(match &course.path {
+            Some(path) => match course
+                .level
+                .fits(&config)
+                .and_then(|()| course.level.save(path))
+            {
+                Ok(()) => format!("Saved to {}", path.display()),
+                Err(err) => format!("Failed to save: {}", err),
+            },
+            None => "No data directory to save to".to_string(),
+        });
+    }
+    if control && keyboard_input.just_pressed(KeyCode::O) {
+        let loaded = match &course.path {
+            Some(path) => Level::load(path).map(|level| (level, path.clone())),
+            None => Err("No data directory to load from".to_string()),
+        };
+        course.message = Some(match loaded {
+            Ok((level, path)) => {
+                course.level = level;
+                course.selected = None;
+                course.dirty = true;
+                format!("Loaded {}", path.display())
+            }
+            Err(err) => format!("Failed to load: {}", err),
+        });
+    }
+
+    if keyboard_input.just_pressed(KeyCode::T) {
+        // Play-test from the selected pair, or from the first one in view
+        let start = course.selected.unwrap_or_else(|| {
+            let positions = course.positions(&config);
+            positions
+                .iter()
+                .position(|x| *x >= course.scroll - config.window_width / 2.0)
+                .unwrap_or_default()
+        });
+        let level = course.from_pipe(start);
+        match level.validate().and_then(|()| level.fits(&config)) {
+            Ok(()) => {
+                *current_level = CurrentLevel {
+                    level: Some(level),
+                    campaign_index: None,
+                };
+                course.testing = true;
+                course.message = None;
+                *game_state = GameState::Waiting;
+                game_reset_events.send(GameResetEvent);
+            }
+            Err(err) => course.message = Some(format!("Can't play-test: {}", err)),
+        }
+    }
+
+    if action_input.just_pressed(InputAction::Menu) {
+        *current_level = CurrentLevel::default();
+        course.selected = None;
+        *game_state = GameState::Menu;
+        game_reset_events.send(GameResetEvent);
+    }
+}
+
+/// Goes back to the editor from a finished play-test instead of the menu.
+fn return_to_editor(
+    mut game_state: ResMut<GameState>,
+    action_input: Res<ActionInput>,
+    course: Res<EditorCourse>,
+) {
+    if course.testing
+        && action_input.just_pressed(InputAction::Menu)
+        && matches!(*game_state, GameState::Dead | GameState::LevelComplete)
+    {
+        *game_state = GameState::Editor;
+    }
+}
+
+#[derive(Component)]
+struct SelectionMarker;
+
+fn spawn_selection_marker(mut commands: Commands, config: Res<GameConfig>) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 0.9, 0.2, 0.3),
+                custom_size: Some(Vec2::new(config.pipe_width + 16.0, config.window_height)),
+                ..Default::default()
+            },
+            // Drawn behind the pipes
+            transform: Transform::from_xyz(0.0, 0.0, -1.0),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(SelectionMarker);
+}
+
+fn update_selection_marker(
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    course: Res<EditorCourse>,
+    mut marker: Query<(&mut Transform, &mut Visibility), With<SelectionMarker>>,
+) {
+    let x = match course.selected {
+        Some(index) if matches!(*game_state, GameState::Editor) => {
+            course.positions(&config).get(index).copied()
+        }
+        _ => None,
+    };
+    for (mut transform, mut visibility) in marker.iter_mut() {
+        visibility.is_visible = x.is_some();
+        if let Some(x) = x {
+            transform.translation.x = x;
+        }
+    }
+}
+
+#[derive(Component)]
+struct EditorText;
+
+fn show_editor_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<GameState>,
+    text: Query<(), With<EditorText>>,
+) {
+    if !matches!(*game_state, GameState::Editor) || !text.is_empty() {
+        return;
+    }
+
+    let style = TextStyle {
+        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(30.0),
+                    left: Val::Px(15.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "Click: add/select and drag  Right click/Del: delete  \
+                                Up/Down: gap size  K: kind\n\
+                                A/D or wheel: scroll  T: play-test  Ctrl+S: save  \
+                                Ctrl+O: load  Esc: menu\n"
+                            .to_string(),
+                        style: style.clone(),
+                    },
+                    TextSection {
+                        value: String::new(),
+                        style: TextStyle {
+                            color: Color::GOLD,
+                            ..style
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(EditorText);
+}
+
+fn update_editor_text(
+    config: Res<GameConfig>,
+    course: Res<EditorCourse>,
+    mut text: Query<&mut Text, With<EditorText>>,
+) {
+    if !course.is_changed() {
+        return;
+    }
+
+    let selected = course.selected.and_then(|index| {
+        let pipe = course.level.pipes.get(index)?;
+        Some(format!(
+            "Pipe {}/{}: gap {:.0}, y {:.2}, {:?}\n",
+            index + 1,
+            course.level.pipes.len(),
+            pipe.gap.unwrap_or(config.pipe_gap),
+            pipe.y,
+            pipe.kind
+        ))
+    });
+    for mut text in text.iter_mut() {
+        text.sections[1].value = format!(
+            "{}{}",
+            selected.as_deref().unwrap_or_default(),
+            course.message.as_deref().unwrap_or_default()
+        );
+    }
+}
+
+fn hide_editor_text(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    text: Query<Entity, With<EditorText>>,
+) {
+    if !matches!(*game_state, GameState::Editor) {
+        for entity in text.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A course of pipe pairs at `spacings` from each other, with gaps at increasing heights to
+    /// tell them apart.
+    fn course(spacings: &[f32]) -> EditorCourse {
+        EditorCourse {
+            level: Level {
+                pipes: spacings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, spacing)| LevelPipe {
+                        spacing: *spacing,
+                        y: i as f32 / 10.0,
+                        ..new_course().pipes[0]
+                    })
+                    .collect(),
+                ..new_course()
+            },
+            path: None,
+            selected: None,
+            drag_offset: None,
+            scroll: 0.0,
+            testing: false,
+            dirty: false,
+            message: None,
+        }
+    }
+
+    fn spacings_and_heights(level: &Level) -> Vec<(f32, f32)> {
+        level
+            .pipes
+            .iter()
+            .map(|pipe| (pipe.spacing, pipe.y))
+            .collect()
+    }
+
+    #[test]
+    fn moved_pipes_stay_in_order_and_selected() {
+        let config = GameConfig::default();
+        let mut course = course(&[0.0, 100.0, 100.0]);
+        course.selected = Some(0);
+
+        let x = config.first_pipe_x;
+        course.set_positions(&config, vec![x + 150.0, x + 100.0, x + 200.0]);
+        assert_eq!(
+            spacings_and_heights(&course.level),
+            vec![(100.0, 0.1), (50.0, 0.0), (50.0, 0.2)]
+        );
+        assert_eq!(course.selected, Some(1));
+        assert!(course.dirty);
+    }
+
+    #[test]
+    fn pipes_cannot_be_moved_before_the_first_pipe_position() {
+        let config = GameConfig::default();
+        let mut course = course(&[50.0]);
+        course.set_positions(&config, vec![config.first_pipe_x - 100.0]);
+        assert_eq!(spacings_and_heights(&course.level), vec![(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn play_tests_start_from_the_chosen_pipe() {
+        let course = course(&[20.0, 100.0, 100.0]);
+        assert_eq!(
+            spacings_and_heights(&course.from_pipe(0)),
+            vec![(0.0, 0.0), (100.0, 0.1), (100.0, 0.2)]
+        );
+        assert_eq!(
+            spacings_and_heights(&course.from_pipe(1)),
+            vec![(0.0, 0.1), (100.0, 0.2)]
+        );
+        // Starting past the end still plays the last pair
+        assert_eq!(spacings_and_heights(&course.from_pipe(9)), vec![(0.0, 0.2)]);
+    }
+
+    #[test]
+    fn gap_positions_invert_gap_offsets_within_the_playable_height() {
+        let config = GameConfig::default();
+        for y in [-1.0, -0.5, 0.0, 0.5, 1.0] {
+            assert_eq!(gap_position(&config, gap_offset(&config, y)), y);
+        }
+        assert_eq!(gap_position(&config, config.window_height), 1.0);
+        assert_eq!(gap_position(&config, -config.window_height), -1.0);
+    }
+
+    #[test]
+    fn the_last_pipe_cannot_be_removed() {
+        let config = GameConfig::default();
+        let mut course = course(&[0.0, 100.0]);
+        remove_pipe(&mut course, &config, 0);
+        assert_eq!(spacings_and_heights(&course.level), vec![(100.0, 0.1)]);
+
+        remove_pipe(&mut course, &config, 0);
+        assert_eq!(course.level.pipes.len(), 1);
+        assert!(course.message.is_some());
+    }
+}
@@ -0,0 +1,167 @@
+use bevy::{app::ManualEventReader, ecs::event::Events, prelude::*};
+use heron::prelude::*;
+
+use crate::{
+    bird::Bird,
+    config::GameConfig,
+    controls::{ActionInput, InputAction},
+    game_state::GameState,
+    headless_app,
+    pipes::{next_pipe_gap, PipePair},
+    simulation::GameRng,
+    DiedEvent, GameResetEvent, IncreaseScoreEvent,
+};
+
+/// Reward given for every pipe passed.
+pub const PIPE_REWARD: f32 = 1.0;
+/// Reward given when the bird dies.
+pub const DEATH_REWARD: f32 = -1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Flap,
+    NoFlap,
+}
+
+/// What an agent can see of the game after each step.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Observation {
+    pub bird_y: f32,
+    pub bird_velocity_y: f32,
+    /// Horizontal distance from the bird to the centre of the next pipe pair.
+    pub pipe_distance_x: f32,
+    /// Height of the centre of the next pipe pair's gap.
+    pub pipe_gap_y: f32,
+}
+
+/// A gym-style environment running a headless, deterministic game.
+///
+/// Every call to [`FlappyEnv::step`] advances the simulation by exactly one fixed tick, until
+/// the run is over and the environment has to be [reset](FlappyEnv::reset).
+pub struct FlappyEnv {
+    app: App,
+    increase_score_reader: ManualEventReader<IncreaseScoreEvent>,
+    died_reader: ManualEventReader<DiedEvent>,
+}
+
+impl FlappyEnv {
+    pub fn new(seed: u64) -> Self {
+        let mut env = FlappyEnv {
+            app: headless_app(seed),
+            increase_score_reader: Default::default(),
+            died_reader: Default::default(),
+        };
+        env.start();
+        env
+    }
+
+    /// Starts a new run with pipes generated from `seed`, as if the environment was new.
+    ///
+    /// The run is already playing when this returns, as starting it takes a flap.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        let world = &mut self.app.world;
+        world
+            .get_resource_mut::<GameRng>()
+            .expect("missing GameRng resource")
+            .reseed(seed);
+        world.insert_resource(GameState::Waiting);
+        world
+            .get_resource_mut::<Events<GameResetEvent>>()
+            .expect("missing GameResetEvent events")
+            .send(GameResetEvent);
+        self.start()
+    }
+
+    /// Starts the run once the app is waiting for the first flap.
+    fn start(&mut self) -> Observation {
+        // Runs the startup systems, or the reset. Nothing that happened before the run started
+        // is rewarded.
+        self.app.update();
+        self.reward();
+        self.step(Action::Flap).0
+    }
+
+    /// Applies `action` and advances one tick, returning the new observation, the reward
+    /// earned during the tick and whether the run is over.
+    ///
+    /// Once the run is over, steps don't advance the game any more and return the final
+    /// observation with no reward.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        if self.is_done() {
+            return (self.observe(), 0.0, true);
+        }
+
+        if action == Action::Flap {
+            self.app
+                .world
+                .get_resource_mut::<ActionInput>()
+                .expect("missing ActionInput resource")
+                .press(InputAction::Flap);
+        }
+        self.app.update();
+
+        let reward = self.reward();
+        (self.observe(), reward, self.is_done())
+    }
+
+    /// Reward earned since the last call.
+    fn reward(&mut self) -> f32 {
+        let mut reward = 0.0;
+        let world = &self.app.world;
+        let increase_score_events = world
+            .get_resource::<Events<IncreaseScoreEvent>>()
+            .expect("missing IncreaseScoreEvent events");
+        reward += PIPE_REWARD
+            * self
+                .increase_score_reader
+                .iter(increase_score_events)
+                .count() as f32;
+        let died_events = world
+            .get_resource::<Events<DiedEvent>>()
+            .expect("missing DiedEvent events");
+        reward += DEATH_REWARD * self.died_reader.iter(died_events).count() as f32;
+        reward
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        let world = &mut self.app.world;
+        let config = world
+            .get_resource::<GameConfig>()
+            .expect("missing GameConfig resource")
+            .clone();
+        let mut bird = world.query_filtered::<(&Transform, &Velocity), With<Bird>>();
+        let mut pipe_pairs = world.query_filtered::<&Transform, With<PipePair>>();
+
+        let (bird_transform, bird_velocity) = match bird.iter(world).next() {
+            Some(bird) => bird,
+            None => return Observation::default(),
+        };
+        let bird_pos = bird_transform.translation;
+        let bird_velocity_y = bird_velocity.linear.y;
+        let gap = next_pipe_gap(bird_pos.x, config.pipe_width, pipe_pairs.iter(world))
+            .unwrap_or_else(|| Vec2::new(bird_pos.x + config.window_width, 0.0));
+
+        Observation {
+            bird_y: bird_pos.y,
+            bird_velocity_y,
+            pipe_distance_x: gap.x - bird_pos.x,
+            pipe_gap_y: gap.y,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self.app.world.get_resource::<GameState>(),
+            Some(GameState::Dead | GameState::LevelComplete)
+        )
+    }
+
+    /// The underlying app, for inspecting anything not covered by [`Observation`].
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+}
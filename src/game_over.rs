@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::{
+    autopilot::Autopilot,
+    controls::{InputAction, InputMap},
+    game_state::GameState,
+    high_scores::{is_ranked, HighScores},
+    level::CurrentLevel,
+    replay::ReplayPlayback,
+    score::Score,
+    ui::{panel_line, spawn_panel},
+};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(show_game_over_panel)
+            .add_system(hide_game_over_panel);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    /// Returns the best medal earned by `score`, if any.
+    pub fn for_score(score: u32) -> Option<Medal> {
+        match score {
+            40.. => Some(Medal::Platinum),
+            30.. => Some(Medal::Gold),
+            20.. => Some(Medal::Silver),
+            10.. => Some(Medal::Bronze),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Medal::Bronze => "Bronze",
+            Medal::Silver => "Silver",
+            Medal::Gold => "Gold",
+            Medal::Platinum => "Platinum",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Medal::Bronze => Color::rgb(0.8, 0.5, 0.2),
+            Medal::Silver => Color::SILVER,
+            Medal::Gold => Color::GOLD,
+            Medal::Platinum => Color::rgb(0.9, 0.95, 1.0),
+        }
+    }
+}
+
+#[derive(Component)]
+struct GameOverPanel;
+
+fn show_game_over_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<GameState>,
+    high_scores: Res<HighScores>,
+    current_level: Res<CurrentLevel>,
+    autopilot: Res<Autopilot>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    input_map: Res<InputMap>,
+    score: Query<&Score>,
+    panel: Query<(), With<GameOverPanel>>,
+) {
+    if !matches!(*game_state, GameState::Dead) || !panel.is_empty() {
+        return;
+    }
+
+    let score = score.get_single().map(|score| score.0).unwrap_or(0);
+    let mut best = high_scores.best().unwrap_or(0);
+    // The high-score table may not have been updated with this run yet
+    if is_ranked(&current_level, &autopilot, replay_playback.as_deref()) {
+        best = best.max(score);
+    }
+
+    let title_font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let value_font = asset_server.load("fonts/FiraMono-Medium.ttf");
+    // Levels aren't ranked, so show which one was being played instead
+    let subtitle = match &current_level.level {
+        Some(level) => level.name.clone(),
+        None => format!("Best: {}", best),
+    };
+
+    let mut lines = vec![
+        panel_line("Game Over".to_string(), &title_font, 72.0, Color::WHITE),
+        panel_line(format!("Score: {}", score), &value_font, 40.0, Color::WHITE),
+        panel_line(subtitle, &value_font, 40.0, Color::GOLD),
+    ];
+    if let Some(medal) = Medal::for_score(score) {
+        lines.push(panel_line(
+            format!("{} medal", medal.name()),
+            &title_font,
+            40.0,
+            medal.color(),
+        ));
+    }
+    lines.push(panel_line(
+        format!(
+            "Press {} to retry",
+            input_map.describe_primary(InputAction::Flap)
+        ),
+        &title_font,
+        28.0,
+        Color::WHITE,
+    ));
+    lines.push(panel_line(
+        format!(
+            "Press {} for the menu",
+            input_map.describe_primary(InputAction::Menu)
+        ),
+        &title_font,
+        28.0,
+        Color::WHITE,
+    ));
+    spawn_panel(&mut commands, GameOverPanel, lines);
+}
+
+fn hide_game_over_panel(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    panel: Query<Entity, With<GameOverPanel>>,
+) {
+    if !matches!(*game_state, GameState::Dead) {
+        for entity in panel.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn medals_start_at_their_thresholds() {
+        assert_eq!(Medal::for_score(0), None);
+        assert_eq!(Medal::for_score(9), None);
+        assert_eq!(Medal::for_score(10), Some(Medal::Bronze));
+        assert_eq!(Medal::for_score(19), Some(Medal::Bronze));
+        assert_eq!(Medal::for_score(20), Some(Medal::Silver));
+        assert_eq!(Medal::for_score(29), Some(Medal::Silver));
+        assert_eq!(Medal::for_score(30), Some(Medal::Gold));
+        assert_eq!(Medal::for_score(39), Some(Medal::Gold));
+        assert_eq!(Medal::for_score(40), Some(Medal::Platinum));
+        assert_eq!(Medal::for_score(u32::MAX), Some(Medal::Platinum));
+    }
+}
@@ -1,12 +1,15 @@
 use bevy::{ecs::schedule::ShouldRun, prelude::*};
 
-use crate::{DiedEvent, GameResetEvent};
+use crate::{
+    controls::{ActionInput, InputAction},
+    DiedEvent, GameResetEvent,
+};
 
 pub struct GameStatePlugin;
 
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(GameState::Waiting)
+        app.init_resource::<GameState>()
             .add_event::<GameStartedEvent>()
             .add_system_set(
                 SystemSet::new()
@@ -21,9 +24,23 @@ impl Plugin for GameStatePlugin {
 pub struct GameStateLabel;
 
 pub enum GameState {
+    /// The title screen and main menu.
+    Menu,
     Waiting,
     Playing,
+    /// A run that has been paused, freezing the whole simulation.
+    Paused,
     Dead,
+    /// The bird crossed the finish line of a level.
+    LevelComplete,
+    /// Building a course in the level editor.
+    Editor,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState::Waiting
+    }
 }
 
 pub struct GameStartedEvent;
@@ -36,18 +53,29 @@ fn handle_died_event(mut game_state: ResMut<GameState>, mut died_events: EventRe
 
 fn handle_restart_input(
     mut game_state: ResMut<GameState>,
-    keyboard_input: Res<Input<KeyCode>>,
+    action_input: Res<ActionInput>,
     mut game_reset_events: EventWriter<GameResetEvent>,
     mut game_started_events: EventWriter<GameStartedEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if action_input.just_pressed(InputAction::Restart)
+        && matches!(
+            *game_state,
+            GameState::Playing | GameState::Dead | GameState::LevelComplete
+        )
+    {
+        *game_state = GameState::Waiting;
+        game_reset_events.send(GameResetEvent);
+        return;
+    }
+
+    if action_input.just_pressed(InputAction::Flap) {
         match *game_state {
             GameState::Waiting => {
                 *game_state = GameState::Playing;
                 game_started_events.send(GameStartedEvent);
             }
-            GameState::Playing => {}
-            GameState::Dead => {
+            GameState::Menu | GameState::Playing | GameState::Paused | GameState::Editor => {}
+            GameState::Dead | GameState::LevelComplete => {
                 *game_state = GameState::Waiting;
                 game_reset_events.send(GameResetEvent);
             }
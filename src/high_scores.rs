@@ -0,0 +1,310 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    autopilot::Autopilot,
+    camera::ScaledText,
+    game_state::{GameStartedEvent, GameState},
+    level::CurrentLevel,
+    replay::ReplayPlayback,
+    score::Score,
+    simulation::{GameRng, Timestep},
+    storage, DiedEvent,
+};
+
+/// Number of scores kept in the high-score table.
+pub const MAX_HIGH_SCORES: usize = 10;
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        let path = storage::data_path("high_scores.ron");
+        let high_scores = path.as_deref().map(HighScores::load).unwrap_or_default();
+
+        app.insert_resource(high_scores)
+            .insert_resource(HighScoresPath(path))
+            .init_resource::<RunTime>()
+            .add_startup_system(setup_best_score)
+            .add_system(time_run)
+            .add_system(handle_died)
+            .add_system(update_best_score_text);
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    /// Seconds since the Unix epoch when the run ended.
+    pub date: u64,
+    /// Seed the run's pipes were generated from, which plays the same course with `--seed`.
+    pub seed: u64,
+    /// Length of the run in seconds, not counting time spent paused.
+    pub duration: f32,
+}
+
+/// The best scores, highest first.
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Loads the table from `path`, starting a new one if it's missing or corrupt. Entries are
+    /// put back in order, in case the file was edited by hand.
+    pub fn load(path: &Path) -> Self {
+        let mut high_scores: HighScores = storage::load_or_default(path);
+        high_scores.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        high_scores.entries.truncate(MAX_HIGH_SCORES);
+        high_scores
+    }
+
+    pub fn best(&self) -> Option<u32> {
+        self.entries.first().map(|entry| entry.score)
+    }
+
+    /// Inserts `entry` in order, returning its rank if it made the table. Runs that didn't
+    /// pass a single pipe never do.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        if entry.score == 0 {
+            return None;
+        }
+
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+/// Whether the current run goes in the high-score table.
+pub fn is_ranked(
+    current_level: &CurrentLevel,
+    autopilot: &Autopilot,
+    replay_playback: Option<&ReplayPlayback>,
+) -> bool {
+    // Only the endless mode is ranked, as levels end after a fixed number of pipes. Neither are
+    // runs played by the autopilot, or replays of runs that were already played.
+    current_level.level.is_none() && !autopilot.enabled && replay_playback.is_none()
+}
+
+/// Where the high-score table is saved, if the platform has a data directory.
+struct HighScoresPath(Option<PathBuf>);
+
+/// Simulated seconds spent playing the current run, not counting time spent paused.
+#[derive(Default)]
+struct RunTime(f32);
+
+#[derive(Component)]
+struct BestScoreText;
+
+fn setup_best_score(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.0),
+                    right: Val::Px(15.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "Best: ".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 60.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: high_scores.best().unwrap_or(0).to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                            font_size: 60.0,
+                            color: Color::GOLD,
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ScaledText { font_size: 60.0 })
+        .insert(BestScoreText);
+}
+
+fn time_run(
+    mut game_started_events: EventReader<GameStartedEvent>,
+    time: Res<Time>,
+    timestep: Res<Timestep>,
+    game_state: Res<GameState>,
+    mut run_time: ResMut<RunTime>,
+) {
+    if game_started_events.iter().next().is_some() {
+        run_time.0 = 0.0;
+    }
+    if matches!(*game_state, GameState::Playing) {
+        run_time.0 += timestep.delta_seconds(&time);
+    }
+}
+
+fn handle_died(
+    mut died_events: EventReader<DiedEvent>,
+    run_time: Res<RunTime>,
+    rng: Res<GameRng>,
+    score: Query<&Score>,
+    mut high_scores: ResMut<HighScores>,
+    path: Res<HighScoresPath>,
+    current_level: Res<CurrentLevel>,
+    autopilot: Res<Autopilot>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+) {
+    if died_events.iter().next().is_none()
+        || !is_ranked(&current_level, &autopilot, replay_playback.as_deref())
+    {
+        return;
+    }
+
+    let score = match score.get_single() {
+        Ok(score) => score.0,
+        Err(_) => return,
+    };
+    let date = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default();
+    let entry = HighScore {
+        score,
+        date,
+        seed: rng.run_seed(),
+        duration: run_time.0,
+    };
+
+    if high_scores.insert(entry).is_some() {
+        if let Some(path) = &path.0 {
+            if let Err(err) = storage::save(path, &*high_scores) {
+                error!("failed to save high scores: {}", err);
+            }
+        }
+    }
+}
+
+fn update_best_score_text(
+    high_scores: Res<HighScores>,
+    mut best_score_text: Query<&mut Text, With<BestScoreText>>,
+) {
+    if high_scores.is_changed() {
+        for mut text in best_score_text.iter_mut() {
+            text.sections[1].value = high_scores.best().unwrap_or(0).to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::temp_path;
+
+    fn entry(score: u32, seed: u64) -> HighScore {
+        HighScore {
+            score,
+            date: 0,
+            seed,
+            duration: 0.0,
+        }
+    }
+
+    fn scores(high_scores: &HighScores) -> Vec<(u32, u64)> {
+        high_scores
+            .entries
+            .iter()
+            .map(|entry| (entry.score, entry.seed))
+            .collect()
+    }
+
+    fn load_str(name: &str, contents: &str) -> HighScores {
+        storage::load_temp(name, contents, HighScores::load)
+    }
+
+    #[test]
+    fn scores_are_ranked_highest_first_and_ties_after_older_runs() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(entry(5, 1)), Some(0));
+        assert_eq!(high_scores.insert(entry(9, 2)), Some(0));
+        assert_eq!(high_scores.insert(entry(5, 3)), Some(2));
+        assert_eq!(high_scores.insert(entry(7, 4)), Some(1));
+        assert_eq!(scores(&high_scores), vec![(9, 2), (7, 4), (5, 1), (5, 3)]);
+        assert_eq!(high_scores.best(), Some(9));
+    }
+
+    #[test]
+    fn only_the_best_scores_are_kept() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=MAX_HIGH_SCORES as u32 {
+            high_scores.insert(entry(score, 0));
+        }
+        assert_eq!(high_scores.insert(entry(1, 0)), None);
+
+        assert_eq!(high_scores.insert(entry(100, 0)), Some(0));
+        assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.entries.last().unwrap().score, 2);
+    }
+
+    #[test]
+    fn runs_that_did_not_score_are_not_ranked() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(entry(0, 0)), None);
+        assert!(high_scores.entries.is_empty());
+        assert_eq!(high_scores.best(), None);
+    }
+
+    #[test]
+    fn missing_and_corrupt_files_start_a_new_table() {
+        assert!(HighScores::load(&temp_path("missing-high-scores"))
+            .entries
+            .is_empty());
+        assert!(load_str("corrupt-high-scores", "(entries: [(score: ")
+            .entries
+            .is_empty());
+    }
+
+    #[test]
+    fn loaded_tables_are_sorted_and_truncated() {
+        let entries: Vec<String> = (1..=MAX_HIGH_SCORES as u32 + 2)
+            .map(|score| {
+                format!(
+                    "(score: {}, date: 0, seed: {}, duration: 0.0)",
+                    score, score
+                )
+            })
+            .collect();
+        let contents = format!("(entries: [{}])", entries.join(", "));
+        let high_scores = load_str("unsorted-high-scores", &contents);
+
+        assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+        assert_eq!(high_scores.best(), Some(MAX_HIGH_SCORES as u32 + 2));
+        assert_eq!(high_scores.entries.last().unwrap().score, 3);
+    }
+}
@@ -0,0 +1,441 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bird::Bird,
+    config::GameConfig,
+    controls::{ActionInput, InputAction, InputMap},
+    game_state::{GameState, GameStateLabel},
+    pipes::{FinishLine, PipeKind},
+    score::Score,
+    storage,
+    ui::{panel_line, spawn_panel},
+    GameResetEvent,
+};
+
+/// Folder of the campaign's level files, relative to the assets folder.
+pub const LEVELS_FOLDER: &str = "levels";
+
+/// Extension of level files, which contain a RON [`Level`].
+pub const LEVEL_EXTENSION: &str = "level";
+
+/// Plays the [`CurrentLevel`], if any, and ends it when the bird crosses the finish line.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentLevel>()
+            .add_event::<LevelCompleteEvent>()
+            .add_system(check_level_complete.before(GameStateLabel));
+    }
+}
+
+/// A hand-authored, finite course of pipe pairs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "0f3c9a2e-5b7d-4e61-8a94-3c1d2e7f6b58"]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub name: String,
+    pub pipes: Vec<LevelPipe>,
+    /// Distance from the last pipe pair to the finish line.
+    #[serde(default = "default_finish")]
+    pub finish: f32,
+}
+
+fn default_finish() -> f32 {
+    200.0
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelPipe {
+    /// Horizontal distance from the previous pipe pair, or from `first_pipe_x` for the first one.
+    pub spacing: f32,
+    /// Height of the gap's centre, from -1 at the bottom to 1 at the top of the playable height.
+    #[serde(default)]
+    pub y: f32,
+    /// Height of the gap, or `None` for the configured `pipe_gap`.
+    #[serde(default)]
+    pub gap: Option<f32>,
+    #[serde(default)]
+    pub kind: PipeKind,
+    /// Pipe speed multiplier from when this pair is the next one to pass, until changed again.
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+impl Level {
+    pub fn from_ron(contents: &str) -> Result<Self, String> {
+        let level: Level = ron::de::from_str(contents).map_err(|err| err.to_string())?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_ron(&contents)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        self.validate()?;
+        storage::save(path, self).map_err(|err| err.to_string())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pipes.is_empty() {
+            return Err("a level needs at least one pipe pair".to_string());
+        }
+        if !(self.finish.is_finite() && self.finish >= 0.0) {
+            return Err(format!("finish must not be negative, got {}", self.finish));
+        }
+
+        for (i, pipe) in self.pipes.iter().enumerate() {
+            if !(pipe.spacing.is_finite() && pipe.spacing >= 0.0) {
+                return Err(format!("pipe {} has a negative spacing", i + 1));
+            }
+            if !(-1.0..=1.0).contains(&pipe.y) {
+                return Err(format!("pipe {} has y outside -1 to 1", i + 1));
+            }
+            if matches!(pipe.gap, Some(gap) if !(gap.is_finite() && gap > 0.0)) {
+                return Err(format!("pipe {} must have a gap above zero", i + 1));
+            }
+            if matches!(pipe.speed, Some(speed) if !(speed.is_finite() && speed > 0.0)) {
+                return Err(format!("pipe {} must have a speed above zero", i + 1));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the gaps fit in the window of `config`, like [`GameConfig::validate`] does for
+    /// `pipe_gap`. Levels are loaded as assets without the config, so this is checked before
+    /// they're played or saved.
+    pub fn fits(&self, config: &GameConfig) -> Result<(), String> {
+        for (i, pipe) in self.pipes.iter().enumerate() {
+            // Open/close pairs only ever narrow from their gap, so the open gap is the one that has
+            // to fit
+            let gap = pipe.gap.unwrap_or(config.pipe_gap);
+            if gap >= config.window_height {
+                return Err(format!(
+                    "pipe {} must have a gap less than window_height ({})",
+                    i + 1,
+                    config.window_height
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pipe speed multiplier once `passed` pipe pairs have been passed.
+    pub fn speed_at(&self, passed: u32) -> f32 {
+        self.pipes
+            .iter()
+            .take(passed as usize + 1)
+            .filter_map(|pipe| pipe.speed)
+            .last()
+            .unwrap_or(1.0)
+    }
+}
+
+/// The level being played, which replaces the endless pipes when set.
+///
+/// Changes take effect on the next [`GameResetEvent`].
+#[derive(Default)]
+pub struct CurrentLevel {
+    pub level: Option<Level>,
+    /// Position of the level in the campaign, when playing through the levels in order.
+    pub campaign_index: Option<usize>,
+}
+
+pub struct LevelCompleteEvent;
+
+fn check_level_complete(
+    mut game_state: ResMut<GameState>,
+    current_level: Res<CurrentLevel>,
+    bird: Query<&Transform, With<Bird>>,
+    finish_lines: Query<&Transform, With<FinishLine>>,
+    mut level_complete_events: EventWriter<LevelCompleteEvent>,
+) {
+    // The finish line is kept out of the way rather than despawned in the endless mode
+    if !matches!(*game_state, GameState::Playing) || current_level.level.is_none() {
+        return;
+    }
+
+    if let (Ok(bird), Ok(finish_line)) = (bird.get_single(), finish_lines.get_single()) {
+        if finish_line.translation.x <= bird.translation.x {
+            *game_state = GameState::LevelComplete;
+            level_complete_events.send(LevelCompleteEvent);
+        }
+    }
+}
+
+/// Loads the campaign from [`LEVELS_FOLDER`] and shows the level complete screen.
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
+            .add_startup_system(load_levels)
+            .add_system(advance_campaign.before(GameStateLabel))
+            .add_system(show_level_complete_panel)
+            .add_system(hide_level_complete_panel);
+    }
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let level = Level::from_ron(std::str::from_utf8(bytes)?).map_err(anyhow::Error::msg)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[LEVEL_EXTENSION]
+    }
+}
+
+/// The campaign's levels, in the order of their file names.
+#[derive(Default)]
+pub struct LevelList {
+    pub levels: Vec<Handle<Level>>,
+}
+
+impl LevelList {
+    /// Returns a copy of the level at `index`, if it has finished loading and fits `config`.
+    pub fn get(&self, index: usize, levels: &Assets<Level>, config: &GameConfig) -> Option<Level> {
+        let level = levels.get(self.levels.get(index)?)?;
+        match level.fits(config) {
+            Ok(()) => Some(level.clone()),
+            Err(err) => {
+                warn!("can't play level {}: {}", level.name, err);
+                None
+            }
+        }
+    }
+}
+
+fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut levels = match asset_server.load_folder(LEVELS_FOLDER) {
+        Ok(handles) => handles
+            .into_iter()
+            .filter_map(|handle| {
+                let path = PathBuf::from(asset_server.get_handle_path(&handle)?.path());
+                Some((path, handle.typed::<Level>()))
+            })
+            .collect(),
+        Err(err) => {
+            warn!("failed to load levels: {:?}", err);
+            Vec::new()
+        }
+    };
+    levels.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    commands.insert_resource(LevelList {
+        levels: levels.into_iter().map(|(_, handle)| handle).collect(),
+    });
+}
+
+/// Moves on to the next level of the campaign when flapping on the level complete screen,
+/// or back to the menu after the last one.
+fn advance_campaign(
+    mut game_state: ResMut<GameState>,
+    action_input: Res<ActionInput>,
+    config: Res<GameConfig>,
+    level_list: Res<LevelList>,
+    levels: Res<Assets<Level>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+) {
+    if !matches!(*game_state, GameState::LevelComplete)
+        || !action_input.just_pressed(InputAction::Flap)
+    {
+        return;
+    }
+
+    // Outside of the campaign, the level is restarted by the game state systems
+    if let Some(index) = current_level.campaign_index {
+        match level_list.get(index + 1, &levels, &config) {
+            Some(level) => {
+                current_level.level = Some(level);
+                current_level.campaign_index = Some(index + 1);
+            }
+            None => {
+                *current_level = CurrentLevel::default();
+                *game_state = GameState::Menu;
+                game_reset_events.send(GameResetEvent);
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct LevelCompletePanel;
+
+fn show_level_complete_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<GameState>,
+    current_level: Res<CurrentLevel>,
+    level_list: Res<LevelList>,
+    input_map: Res<InputMap>,
+    score: Query<&Score>,
+    panel: Query<(), With<LevelCompletePanel>>,
+) {
+    if !matches!(*game_state, GameState::LevelComplete) || !panel.is_empty() {
+        return;
+    }
+
+    let name = current_level
+        .level
+        .as_ref()
+        .map(|level| level.name.clone())
+        .unwrap_or_default();
+    let score = score.get_single().map(|score| score.0).unwrap_or(0);
+    let next = match current_level.campaign_index {
+        Some(index) if index + 1 < level_list.levels.len() => "for the next level",
+        Some(_) => "to finish the campaign",
+        None => "to play again",
+    };
+
+    let title_font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let value_font = asset_server.load("fonts/FiraMono-Medium.ttf");
+    let lines = vec![
+        panel_line(
+            "Level Complete".to_string(),
+            &title_font,
+            72.0,
+            Color::WHITE,
+        ),
+        panel_line(name, &value_font, 40.0, Color::GOLD),
+        panel_line(format!("Score: {}", score), &value_font, 40.0, Color::WHITE),
+        panel_line(
+            format!(
+                "Press {} {}",
+                input_map.describe_primary(InputAction::Flap),
+                next
+            ),
+            &title_font,
+            28.0,
+            Color::WHITE,
+        ),
+        panel_line(
+            format!(
+                "Press {} for the menu",
+                input_map.describe_primary(InputAction::Menu)
+            ),
+            &title_font,
+            28.0,
+            Color::WHITE,
+        ),
+    ];
+    spawn_panel(&mut commands, LevelCompletePanel, lines);
+}
+
+fn hide_level_complete_panel(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    panel: Query<Entity, With<LevelCompletePanel>>,
+) {
+    if !matches!(*game_state, GameState::LevelComplete) {
+        for entity in panel.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipe(speed: Option<f32>) -> LevelPipe {
+        LevelPipe {
+            spacing: 200.0,
+            y: 0.0,
+            gap: None,
+            kind: PipeKind::default(),
+            speed,
+        }
+    }
+
+    fn level(pipes: Vec<LevelPipe>) -> Level {
+        Level {
+            name: "Test".to_string(),
+            pipes,
+            finish: default_finish(),
+        }
+    }
+
+    #[test]
+    fn speed_changes_last_until_changed_again() {
+        let level = level(vec![
+            pipe(None),
+            pipe(Some(1.5)),
+            pipe(None),
+            pipe(Some(0.5)),
+        ]);
+        assert_eq!(level.speed_at(0), 1.0);
+        assert_eq!(level.speed_at(1), 1.5);
+        assert_eq!(level.speed_at(2), 1.5);
+        assert_eq!(level.speed_at(3), 0.5);
+        assert_eq!(level.speed_at(10), 0.5);
+    }
+
+    #[test]
+    fn errors_name_the_pipe_counting_from_one() {
+        let off_screen = LevelPipe {
+            y: 1.5,
+            ..pipe(None)
+        };
+        let err = level(vec![pipe(None), off_screen]).validate().unwrap_err();
+        assert_eq!(err, "pipe 2 has y outside -1 to 1");
+    }
+
+    #[test]
+    fn gaps_have_to_fit_in_the_window() {
+        let config = GameConfig::default();
+        let gap = |gap: f32, kind: PipeKind| LevelPipe {
+            gap: Some(gap),
+            kind,
+            ..pipe(None)
+        };
+
+        level(vec![pipe(None), gap(599.0, PipeKind::Static)])
+            .fits(&config)
+            .unwrap();
+        let err = level(vec![pipe(None), gap(600.0, PipeKind::Static)])
+            .fits(&config)
+            .unwrap_err();
+        assert_eq!(err, "pipe 2 must have a gap less than window_height (600)");
+        assert!(level(vec![gap(600.0, PipeKind::OpenClose)])
+            .fits(&config)
+            .is_err());
+
+        // Pairs without a gap of their own use the configured one
+        let tall_default = GameConfig {
+            pipe_gap: 700.0,
+            ..config
+        };
+        assert!(level(vec![pipe(None)]).fits(&tall_default).is_err());
+    }
+}
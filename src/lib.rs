@@ -0,0 +1,112 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use autopilot::AutopilotPlugin;
+use bevy::{input::InputPlugin, prelude::*, transform::TransformPlugin};
+use bird::BirdPlugin;
+use collisions::CollisionsPlugin;
+use config::GameConfig;
+use controls::ControlsPlugin;
+use difficulty::DifficultyPlugin;
+use game_state::GameStatePlugin;
+use heron::prelude::*;
+use level::LevelPlugin;
+use pipes::PipesPlugin;
+use score::ScorePlugin;
+use simulation::{GameRng, SimulationPlugin, Timestep};
+use theme::Theme;
+use world::WorldPlugin;
+
+pub mod achievements;
+pub mod autopilot;
+pub mod background;
+pub mod bird;
+pub mod camera;
+pub mod collisions;
+pub mod config;
+pub mod controls;
+pub mod difficulty;
+pub mod editor;
+pub mod env;
+pub mod game_over;
+pub mod game_state;
+pub mod high_scores;
+pub mod level;
+pub mod menu;
+pub mod pause;
+pub mod pipes;
+pub mod progress;
+pub mod replay;
+pub mod score;
+pub mod simulation;
+pub mod sounds;
+pub mod storage;
+pub mod theme;
+pub mod ui;
+pub mod world;
+
+#[derive(PhysicsLayer)]
+pub enum Layer {
+    World,
+    Player,
+    PipeGap,
+}
+
+/// Sent when the bird crashes.
+pub struct DiedEvent {
+    pub cause: DeathCause,
+}
+
+/// What the bird crashed into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    Pipe,
+    /// The ground or the ceiling.
+    Bounds,
+}
+
+pub struct FlapEvent;
+pub struct IncreaseScoreEvent;
+pub struct GameResetEvent;
+
+/// Game logic shared by the windowed game and headless simulations.
+///
+/// Contains no rendering, windowing or audio plugins.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameConfig>()
+            .init_resource::<Theme>()
+            .add_plugin(SimulationPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(AutopilotPlugin)
+            .add_plugin(DifficultyPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(PipesPlugin)
+            .add_plugin(BirdPlugin)
+            .add_plugin(WorldPlugin)
+            .add_plugin(CollisionsPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(GameStatePlugin)
+            .add_plugin(PhysicsPlugin::default())
+            .add_event::<DiedEvent>()
+            .add_event::<FlapEvent>()
+            .add_event::<IncreaseScoreEvent>()
+            .add_event::<GameResetEvent>();
+    }
+}
+
+/// Builds an app that runs the full game loop without a window, GPU or audio device.
+///
+/// Headless apps always use a [`Timestep::Fixed`], so the same seed and inputs produce
+/// the same run. Drive it with [`App::update`] and inspect [`App::world`] between frames.
+pub fn headless_app(seed: u64) -> App {
+    let mut app = App::new();
+    app.insert_resource(Timestep::Fixed)
+        .insert_resource(GameRng::new(seed))
+        .add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(GamePlugin);
+    app
+}
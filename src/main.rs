@@ -1,66 +1,150 @@
-#![allow(clippy::type_complexity)]
-
 use bevy::prelude::*;
 use bevy_kira_audio::AudioPlugin;
-use bird::BirdPlugin;
-use camera::CameraPlugin;
-use collisions::CollisionsPlugin;
-use game_state::GameStatePlugin;
-use heron::prelude::*;
-use pipes::PipesPlugin;
-use score::ScorePlugin;
-use sounds::SoundsPlugin;
-use world::WorldPlugin;
-
-mod bird;
-mod camera;
-mod collisions;
-mod game_state;
-mod pipes;
-mod score;
-mod sounds;
-mod world;
+use flappy_burd::{
+    achievements::AchievementsPlugin,
+    autopilot::{AttractModePlugin, Autopilot},
+    background::BackgroundPlugin,
+    camera::CameraPlugin,
+    config::{ConfigReloadPlugin, GameConfig},
+    controls::InputMap,
+    editor::EditorPlugin,
+    game_over::GameOverPlugin,
+    game_state::GameState,
+    headless_app,
+    high_scores::HighScoresPlugin,
+    level::{CampaignPlugin, CurrentLevel, Level},
+    menu::MenuPlugin,
+    pause::PausePlugin,
+    progress::ProgressPlugin,
+    replay::{Replay, ReplayPlugin},
+    simulation::{GameRng, TickRatePlugin, Timestep},
+    sounds::SoundsPlugin,
+    theme::ThemePlugin,
+    GamePlugin,
+};
 
-const WIN_WIDTH: f32 = 1200.0;
-const WIN_HEIGHT: f32 = 600.0;
+/// Returns the value following `name` on the command line, if any.
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    args.next()
+}
 
-#[derive(PhysicsLayer)]
-enum Layer {
-    World,
-    Player,
-    PipeGap,
+fn has_flag(name: &str) -> bool {
+    std::env::args().any(|arg| arg == name)
 }
 
-struct DiedEvent;
-struct FlapEvent;
-struct IncreaseScoreEvent;
-struct GameResetEvent;
+/// Reports a bad command line and exits with the usual status for usage errors. The logger isn't
+/// set up until the app runs, so this goes straight to stderr.
+fn exit_with_error(message: String) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(2);
+}
 
 fn main() {
-    App::new()
-        .insert_resource(WindowDescriptor {
-            title: "Flappy Burd".to_string(),
-            width: WIN_WIDTH,
-            height: WIN_HEIGHT,
-            resizable: false,
-            ..Default::default()
-        })
-        .insert_resource(ClearColor(Color::rgb(0.658, 0.8, 1.0)))
-        .add_plugins(DefaultPlugins)
-        .add_plugin(CameraPlugin)
-        .add_plugin(PipesPlugin)
-        .add_plugin(BirdPlugin)
-        .add_plugin(WorldPlugin)
-        .add_plugin(CollisionsPlugin)
-        .add_plugin(ScorePlugin)
-        .add_plugin(AudioPlugin)
-        .add_plugin(SoundsPlugin)
-        .add_plugin(GameStatePlugin)
-        .add_plugin(PhysicsPlugin::default())
-        .add_event::<DiedEvent>()
-        .add_event::<FlapEvent>()
-        .add_event::<IncreaseScoreEvent>()
-        .add_event::<GameResetEvent>()
-        .insert_resource(Gravity::from(Vec3::new(0.0, -600.0, 0.0)))
-        .run();
+    // Nothing reads the keyboard without a window, so a headless run would wait forever
+    if has_flag("--headless") && !has_flag("--autopilot") && arg_value("--replay").is_none() {
+        exit_with_error("--headless needs --autopilot or --replay to play the game".to_string());
+    }
+
+    let replay_plugin = if let Some(path) = arg_value("--replay") {
+        let replay = Replay::load(&path)
+            .unwrap_or_else(|err| exit_with_error(format!("failed to load {}: {}", path, err)));
+        Some(ReplayPlugin::Playback(replay))
+    } else {
+        arg_value("--record").map(|path| ReplayPlugin::Record(path.into()))
+    };
+
+    let seed = match arg_value("--seed").map(|seed| seed.parse()) {
+        Some(Ok(seed)) => seed,
+        Some(Err(err)) => exit_with_error(format!("invalid --seed: {}", err)),
+        None => rand::random(),
+    };
+
+    let config = GameConfig::load_or_default();
+
+    let current_level = match arg_value("--level") {
+        Some(path) => CurrentLevel {
+            level: Some(
+                Level::load(path.as_ref())
+                    .and_then(|level| level.fits(&config).map(|()| level))
+                    .unwrap_or_else(|err| {
+                        exit_with_error(format!("failed to load {}: {}", path, err))
+                    }),
+            ),
+            campaign_index: None,
+        },
+        None => CurrentLevel::default(),
+    };
+    let play_level = current_level.level.is_some();
+
+    let mut app = if has_flag("--headless") {
+        let mut app = headless_app(seed);
+        app.insert_resource(config).insert_resource(current_level);
+        app
+    } else {
+        // Replays are only reproducible with a fixed timestep
+        let timestep = if has_flag("--deterministic") || replay_plugin.is_some() {
+            Timestep::Fixed
+        } else {
+            Timestep::Variable
+        };
+
+        // Recorded and automated runs skip the menu, as navigating it would shift the ticks.
+        // Courses given on the command line are played straight away too.
+        let game_state = if replay_plugin.is_some() || has_flag("--autopilot") || play_level {
+            GameState::Waiting
+        } else {
+            GameState::Menu
+        };
+
+        let mut app = App::new();
+        app.insert_resource(timestep)
+            .insert_resource(GameRng::new(seed))
+            .insert_resource(game_state)
+            .insert_resource(InputMap::load())
+            .insert_resource(config.clone())
+            .insert_resource(current_level)
+            .insert_resource(WindowDescriptor {
+                title: "Flappy Burd".to_string(),
+                width: config.window_width,
+                height: config.window_height,
+                resizable: true,
+                ..Default::default()
+            })
+            .add_plugins(DefaultPlugins)
+            // Progress first, so locked themes aren't chosen
+            .add_plugin(ProgressPlugin)
+            .add_plugin(ThemePlugin)
+            .add_plugin(CameraPlugin)
+            .add_plugin(BackgroundPlugin)
+            .add_plugin(AudioPlugin)
+            .add_plugin(SoundsPlugin)
+            .add_plugin(HighScoresPlugin)
+            .add_plugin(AchievementsPlugin)
+            .add_plugin(GameOverPlugin)
+            .add_plugin(CampaignPlugin)
+            .add_plugin(EditorPlugin)
+            .add_plugin(MenuPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(ConfigReloadPlugin)
+            .add_plugin(GamePlugin);
+        if timestep == Timestep::Fixed {
+            app.add_plugin(TickRatePlugin);
+        }
+        if replay_plugin.is_none() && !has_flag("--autopilot") {
+            app.add_plugin(AttractModePlugin);
+        }
+        app
+    };
+
+    if has_flag("--autopilot") {
+        app.insert_resource(Autopilot { enabled: true });
+    }
+
+    if let Some(replay_plugin) = replay_plugin {
+        app.add_plugin(replay_plugin);
+    }
+
+    app.run();
 }
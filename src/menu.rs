@@ -0,0 +1,726 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    config::GameConfig,
+    controls::{ActionInput, Binding, InputAction, InputMap},
+    game_state::{GameState, GameStateLabel},
+    high_scores::HighScores,
+    level::{CurrentLevel, Level, LevelList},
+    progress::Progress,
+    sounds::SoundSettings,
+    theme::{Theme, ThemeManifest, ThemeSettings},
+    ui, GameResetEvent,
+};
+
+const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED_BUTTON: Color = Color::rgb(0.25, 0.6, 0.25);
+const LOCKED_TEXT: Color = Color::rgb(0.45, 0.45, 0.45);
+/// Seconds to wait for a new binding before giving up.
+const REBIND_TIMEOUT: f32 = 5.0;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuScreen>()
+            .init_resource::<MenuSelection>()
+            .init_resource::<RebindState>()
+            .add_event::<MenuActivatedEvent>()
+            .add_system(rebuild_menu)
+            .add_system(menu_navigation.label(MenuInputLabel))
+            .add_system(menu_mouse_input.label(MenuInputLabel))
+            .add_system(
+                handle_menu_activated
+                    .after(MenuInputLabel)
+                    .after(GameStateLabel),
+            )
+            .add_system(capture_binding.after(MenuInputLabel))
+            .add_system(highlight_selection)
+            .add_system(return_to_menu.after(GameStateLabel));
+    }
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+struct MenuInputLabel;
+
+/// The menu page shown while in [`GameState::Menu`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuScreen {
+    Main,
+    Levels,
+    /// The bird skins and world themes, locked and unlocked.
+    Collection,
+    HighScores,
+    Settings,
+}
+
+impl Default for MenuScreen {
+    fn default() -> Self {
+        MenuScreen::Main
+    }
+}
+
+impl MenuScreen {
+    fn items(&self, level_count: usize, theme_manifest: &ThemeManifest) -> Vec<MenuItem> {
+        match self {
+            MenuScreen::Main => vec![
+                MenuItem::Play,
+                MenuItem::Levels,
+                MenuItem::Collection,
+                MenuItem::Editor,
+                MenuItem::HighScores,
+                MenuItem::Settings,
+                MenuItem::Quit,
+            ],
+            MenuScreen::Levels => {
+                let mut items = vec![MenuItem::Campaign];
+                items.extend((0..level_count).map(MenuItem::Level));
+                items.push(MenuItem::Back);
+                items
+            }
+            MenuScreen::Collection => {
+                let birds = (0..theme_manifest.birds.len()).map(MenuItem::Bird);
+                let worlds = (0..theme_manifest.worlds.len()).map(MenuItem::World);
+                birds.chain(worlds).chain([MenuItem::Back]).collect()
+            }
+            MenuScreen::HighScores => vec![MenuItem::Back],
+            MenuScreen::Settings => vec![
+                MenuItem::ToggleSound,
+                MenuItem::BirdSkin,
+                MenuItem::WorldTheme,
+                MenuItem::Rebind(InputAction::Flap),
+                MenuItem::Rebind(InputAction::Pause),
+                MenuItem::Rebind(InputAction::Restart),
+                MenuItem::Rebind(InputAction::Menu),
+                MenuItem::ResetBindings,
+                MenuItem::Back,
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    Play,
+    Levels,
+    /// Plays every level in order, starting from the first.
+    Campaign,
+    /// Plays a single level, by its index in the [`LevelList`].
+    Level(usize),
+    Collection,
+    /// Chooses a bird skin, by its index in the [`ThemeManifest`].
+    Bird(usize),
+    /// Chooses a world theme, by its index in the [`ThemeManifest`].
+    World(usize),
+    Editor,
+    HighScores,
+    Settings,
+    Quit,
+    ToggleSound,
+    /// Switches to the next unlocked bird skin in the [`ThemeManifest`].
+    BirdSkin,
+    /// Switches to the next unlocked world theme in the [`ThemeManifest`].
+    WorldTheme,
+    Rebind(InputAction),
+    ResetBindings,
+    Back,
+}
+
+/// Everything the labels of menu items are made from.
+struct LabelContext<'a> {
+    sound_settings: &'a SoundSettings,
+    theme: &'a Theme,
+    theme_manifest: &'a ThemeManifest,
+    progress: &'a Progress,
+    input_map: &'a InputMap,
+    rebind_state: &'a RebindState,
+    level_names: &'a [String],
+}
+
+impl MenuItem {
+    fn label(&self, context: &LabelContext) -> String {
+        let LabelContext {
+            sound_settings,
+            theme,
+            theme_manifest,
+            progress,
+            input_map,
+            rebind_state,
+            level_names,
+        } = context;
+        match self {
+            MenuItem::Play => "Play".to_string(),
+            MenuItem::Levels => "Levels".to_string(),
+            MenuItem::Campaign => "Campaign".to_string(),
+            MenuItem::Level(index) => format!("{}. {}", index + 1, level_names[*index]),
+            MenuItem::Collection => "Collection".to_string(),
+            MenuItem::Bird(index) => {
+                let bird = &theme_manifest.birds[*index];
+                let status = match bird.unlock {
+                    Some(unlock) if !progress.bird_unlocked(bird) => unlock.describe(),
+                    _ if bird.name == theme.bird.name => "Equipped".to_string(),
+                    _ => "Unlocked".to_string(),
+                };
+                format!("{} bird: {}", bird.name, status)
+            }
+            MenuItem::World(index) => {
+                let world = &theme_manifest.worlds[*index];
+                let status = match world.unlock {
+                    Some(unlock) if !progress.world_unlocked(world) => unlock.describe(),
+                    _ if world.name == theme.world.name => "Equipped".to_string(),
+                    _ => "Unlocked".to_string(),
+                };
+                format!("{} theme: {}", world.name, status)
+            }
+            MenuItem::Editor => "Level Editor".to_string(),
+            MenuItem::HighScores => "High Scores".to_string(),
+            MenuItem::Settings => "Settings".to_string(),
+            MenuItem::Quit => "Quit".to_string(),
+            MenuItem::ToggleSound => {
+                format!(
+                    "Sound: {}",
+                    if sound_settings.enabled { "On" } else { "Off" }
+                )
+            }
+            MenuItem::BirdSkin => format!("Bird: {}", theme.bird.name),
+            MenuItem::WorldTheme => format!("Theme: {}", theme.world.name),
+            MenuItem::Rebind(action)
+                if rebind_state.action == Some(*action) && rebind_state.escape_pending =>
+            {
+                format!("{}: press Escape again to bind it...", action.name())
+            }
+            MenuItem::Rebind(action) if rebind_state.action == Some(*action) => {
+                format!("{}: press a key or button...", action.name())
+            }
+            MenuItem::Rebind(action) => {
+                format!("{}: {}", action.name(), input_map.describe(*action))
+            }
+            MenuItem::ResetBindings => "Reset Controls".to_string(),
+            MenuItem::Back => "Back".to_string(),
+        }
+    }
+
+    /// Whether the item is a bird skin or world theme that hasn't been unlocked yet.
+    fn locked(&self, theme_manifest: &ThemeManifest, progress: &Progress) -> bool {
+        match self {
+            MenuItem::Bird(index) => !progress.bird_unlocked(&theme_manifest.birds[*index]),
+            MenuItem::World(index) => !progress.world_unlocked(&theme_manifest.worlds[*index]),
+            _ => false,
+        }
+    }
+}
+
+/// Index of the highlighted item on the current [`MenuScreen`].
+#[derive(Default)]
+struct MenuSelection(usize);
+
+struct MenuActivatedEvent(MenuItem);
+
+/// The action waiting for a new binding on the settings screen, and the result of the last
+/// attempt to rebind one.
+#[derive(Default)]
+struct RebindState {
+    action: Option<InputAction>,
+    /// Escape was pressed once, so pressing it again binds it.
+    escape_pending: bool,
+    message: Option<String>,
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+struct MenuButton {
+    index: usize,
+    item: MenuItem,
+}
+
+fn rebuild_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<GameState>,
+    screen: Res<MenuScreen>,
+    sound_settings: Res<SoundSettings>,
+    theme: Res<Theme>,
+    theme_manifest: Res<ThemeManifest>,
+    progress: Res<Progress>,
+    high_scores: Res<HighScores>,
+    input_map: Res<InputMap>,
+    rebind_state: Res<RebindState>,
+    level_list: Res<LevelList>,
+    levels: Res<Assets<Level>>,
+    mut level_events: EventReader<AssetEvent<Level>>,
+    menu: Query<Entity, With<MenuRoot>>,
+) {
+    // Levels finish loading after the menu is first shown
+    let levels_changed = level_events.iter().next().is_some();
+    if !levels_changed
+        && !game_state.is_changed()
+        && !screen.is_changed()
+        && !sound_settings.is_changed()
+        && !theme.is_changed()
+        && !progress.is_changed()
+        && !high_scores.is_changed()
+        && !input_map.is_changed()
+        && !rebind_state.is_changed()
+    {
+        return;
+    }
+
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !matches!(*game_state, GameState::Menu) {
+        return;
+    }
+
+    let title_font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let value_font = asset_server.load("fonts/FiraMono-Medium.ttf");
+    let text = |value: String, font: &Handle<Font>, font_size: f32| {
+        ui::text(value, font, font_size, Color::WHITE)
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..Default::default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            let title = match *screen {
+                MenuScreen::Main => "Flappy Burd",
+                MenuScreen::Levels => "Levels",
+                MenuScreen::Collection => "Collection",
+                MenuScreen::HighScores => "High Scores",
+                MenuScreen::Settings => "Settings",
+            };
+            parent.spawn_bundle(TextBundle {
+                text: text(title.to_string(), &title_font, 80.0),
+                style: Style {
+                    margin: Rect::all(Val::Px(16.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+
+            if *screen == MenuScreen::HighScores {
+                if high_scores.entries.is_empty() {
+                    parent.spawn_bundle(TextBundle {
+                        text: text("No scores yet".to_string(), &value_font, 32.0),
+                        ..Default::default()
+                    });
+                }
+                for (rank, entry) in high_scores.entries.iter().enumerate() {
+                    let duration = entry.duration as u32;
+                    parent.spawn_bundle(TextBundle {
+                        text: text(
+                            format!(
+                                "{:>2}. {:>4}  {:>2}m {:02}s",
+                                rank + 1,
+                                entry.score,
+                                duration / 60,
+                                duration % 60
+                            ),
+                            &value_font,
+                            32.0,
+                        ),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            if *screen == MenuScreen::Collection {
+                parent.spawn_bundle(TextBundle {
+                    text: text(
+                        format!(
+                            "Best score: {}  Flaps: {}",
+                            high_scores.best().unwrap_or(0),
+                            progress.flaps
+                        ),
+                        &value_font,
+                        24.0,
+                    ),
+                    ..Default::default()
+                });
+            }
+
+            if *screen == MenuScreen::Levels && level_list.levels.is_empty() {
+                parent.spawn_bundle(TextBundle {
+                    text: text("No levels found".to_string(), &value_font, 32.0),
+                    ..Default::default()
+                });
+            }
+
+            if *screen == MenuScreen::Settings {
+                if let Some(message) = &rebind_state.message {
+                    parent.spawn_bundle(TextBundle {
+                        text: text(message.clone(), &value_font, 24.0),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            let level_names: Vec<String> = level_list
+                .levels
+                .iter()
+                .map(|handle| {
+                    levels
+                        .get(handle)
+                        .map_or_else(|| "Loading...".to_string(), |level| level.name.clone())
+                })
+                .collect();
+
+            // Settings, levels and the collection can have many items, so need smaller buttons
+            // to fit on screen
+            let (button_height, font_size) = match *screen {
+                MenuScreen::Settings | MenuScreen::Levels | MenuScreen::Collection => (48.0, 28.0),
+                _ => (64.0, 36.0),
+            };
+            let context = LabelContext {
+                sound_settings: &sound_settings,
+                theme: &theme,
+                theme_manifest: &theme_manifest,
+                progress: &progress,
+                input_map: &input_map,
+                rebind_state: &rebind_state,
+                level_names: &level_names,
+            };
+            let items = screen.items(level_names.len(), &theme_manifest);
+            for (index, item) in items.iter().enumerate() {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            min_size: Size::new(Val::Px(320.0), Val::Px(button_height)),
+                            margin: Rect::all(Val::Px(6.0)),
+                            padding: Rect {
+                                left: Val::Px(16.0),
+                                right: Val::Px(16.0),
+                                ..Default::default()
+                            },
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: NORMAL_BUTTON.into(),
+                        ..Default::default()
+                    })
+                    .insert(MenuButton { index, item: *item })
+                    .with_children(|parent| {
+                        let mut label = text(item.label(&context), &title_font, font_size);
+                        if item.locked(&theme_manifest, &progress) {
+                            label.sections[0].style.color = LOCKED_TEXT;
+                        }
+                        parent.spawn_bundle(TextBundle {
+                            text: label,
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
+
+fn menu_navigation(
+    game_state: Res<GameState>,
+    screen: Res<MenuScreen>,
+    mut selection: ResMut<MenuSelection>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    action_input: Res<ActionInput>,
+    rebind_state: Res<RebindState>,
+    level_list: Res<LevelList>,
+    theme_manifest: Res<ThemeManifest>,
+    mut menu_activated_events: EventWriter<MenuActivatedEvent>,
+) {
+    // While rebinding, every key press belongs to the new binding
+    if !matches!(*game_state, GameState::Menu) || rebind_state.action.is_some() {
+        return;
+    }
+
+    let pressed = |keys: &[KeyCode], button: GamepadButtonType| {
+        keys.iter().any(|key| keyboard_input.just_pressed(*key))
+            || gamepads
+                .iter()
+                .any(|gamepad| gamepad_input.just_pressed(GamepadButton(*gamepad, button)))
+    };
+
+    let items = screen.items(level_list.levels.len(), &theme_manifest);
+    if pressed(&[KeyCode::Up, KeyCode::W], GamepadButtonType::DPadUp) {
+        selection.0 = (selection.0 + items.len() - 1) % items.len();
+    }
+    if pressed(&[KeyCode::Down, KeyCode::S], GamepadButtonType::DPadDown) {
+        selection.0 = (selection.0 + 1) % items.len();
+    }
+    if pressed(&[KeyCode::Return, KeyCode::Space], GamepadButtonType::South) {
+        if let Some(item) = items.get(selection.0) {
+            menu_activated_events.send(MenuActivatedEvent(*item));
+        }
+    }
+    if action_input.just_pressed(InputAction::Menu)
+        || pressed(&[KeyCode::Back], GamepadButtonType::East)
+    {
+        menu_activated_events.send(MenuActivatedEvent(MenuItem::Back));
+    }
+}
+
+fn menu_mouse_input(
+    mut selection: ResMut<MenuSelection>,
+    rebind_state: Res<RebindState>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut menu_activated_events: EventWriter<MenuActivatedEvent>,
+) {
+    if rebind_state.action.is_some() {
+        return;
+    }
+
+    for (interaction, button) in buttons.iter() {
+        match interaction {
+            Interaction::Clicked => menu_activated_events.send(MenuActivatedEvent(button.item)),
+            Interaction::Hovered => {
+                if selection.0 != button.index {
+                    selection.0 = button.index;
+                }
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+fn handle_menu_activated(
+    mut menu_activated_events: EventReader<MenuActivatedEvent>,
+    mut game_state: ResMut<GameState>,
+    mut screen: ResMut<MenuScreen>,
+    mut selection: ResMut<MenuSelection>,
+    mut sound_settings: ResMut<SoundSettings>,
+    // Grouped to stay within the number of parameters a system can have
+    (theme_manifest, mut theme_settings, theme, progress): (
+        Res<ThemeManifest>,
+        ResMut<ThemeSettings>,
+        Res<Theme>,
+        Res<Progress>,
+    ),
+    mut input_map: ResMut<InputMap>,
+    mut rebind_state: ResMut<RebindState>,
+    config: Res<GameConfig>,
+    level_list: Res<LevelList>,
+    levels: Res<Assets<Level>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    for MenuActivatedEvent(item) in menu_activated_events.iter() {
+        if !matches!(*game_state, GameState::Menu) {
+            continue;
+        }
+
+        let next_screen = match item {
+            MenuItem::Play => {
+                // The pipes are respawned in case the last run was a level
+                *current_level = CurrentLevel::default();
+                *game_state = GameState::Waiting;
+                game_reset_events.send(GameResetEvent);
+                MenuScreen::Main
+            }
+            MenuItem::Levels => MenuScreen::Levels,
+            MenuItem::Campaign | MenuItem::Level(_) => {
+                let (index, campaign) = match item {
+                    MenuItem::Level(index) => (*index, false),
+                    _ => (0, true),
+                };
+                // Levels that are still loading or don't fit the window can't be started
+                match level_list.get(index, &levels, &config) {
+                    Some(level) => {
+                        *current_level = CurrentLevel {
+                            level: Some(level),
+                            campaign_index: campaign.then(|| index),
+                        };
+                        *game_state = GameState::Waiting;
+                        game_reset_events.send(GameResetEvent);
+                        MenuScreen::Main
+                    }
+                    None => *screen,
+                }
+            }
+            MenuItem::Collection => MenuScreen::Collection,
+            MenuItem::Bird(index) => {
+                // Locked items can be looked at, but not chosen
+                let bird = &theme_manifest.birds[*index];
+                if progress.bird_unlocked(bird) && theme_settings.bird != bird.name {
+                    theme_settings.bird = bird.name.clone();
+                    theme_settings.save();
+                }
+                *screen
+            }
+            MenuItem::World(index) => {
+                let world = &theme_manifest.worlds[*index];
+                if progress.world_unlocked(world) && theme_settings.world != world.name {
+                    theme_settings.world = world.name.clone();
+                    theme_settings.save();
+                }
+                *screen
+            }
+            MenuItem::Editor => {
+                *game_state = GameState::Editor;
+                MenuScreen::Main
+            }
+            MenuItem::HighScores => MenuScreen::HighScores,
+            MenuItem::Settings => MenuScreen::Settings,
+            MenuItem::Quit => {
+                app_exit_events.send(AppExit);
+                *screen
+            }
+            MenuItem::ToggleSound => {
+                sound_settings.enabled = !sound_settings.enabled;
+                sound_settings.save();
+                *screen
+            }
+            MenuItem::BirdSkin => {
+                let next =
+                    theme_manifest.next_bird(&theme.bird.name, |bird| progress.bird_unlocked(bird));
+                if let Some(bird) = next {
+                    theme_settings.bird = bird.name.clone();
+                    theme_settings.save();
+                }
+                *screen
+            }
+            MenuItem::WorldTheme => {
+                let next = theme_manifest
+                    .next_world(&theme.world.name, |world| progress.world_unlocked(world));
+                if let Some(world) = next {
+                    theme_settings.world = world.name.clone();
+                    theme_settings.save();
+                }
+                *screen
+            }
+            MenuItem::Rebind(action) => {
+                rebind_state.action = Some(*action);
+                rebind_state.escape_pending = false;
+                rebind_state.message = None;
+                *screen
+            }
+            MenuItem::ResetBindings => {
+                *input_map = InputMap::default();
+                input_map.save();
+                rebind_state.message = Some("Controls reset to defaults".to_string());
+                *screen
+            }
+            MenuItem::Back => MenuScreen::Main,
+        };
+
+        if *screen != next_screen {
+            *screen = next_screen;
+            selection.0 = 0;
+        }
+    }
+}
+
+/// Binds the next key, mouse button or gamepad button pressed to the action being rebound.
+/// Escape has to be pressed twice to be bound, and rebinding is cancelled after
+/// [`REBIND_TIMEOUT`] seconds without a new binding.
+fn capture_binding(
+    time: Res<Time>,
+    mut rebind_state: ResMut<RebindState>,
+    mut input_map: ResMut<InputMap>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut waited: Local<f32>,
+) {
+    // Skip the frame rebinding started on, so the press that selected it isn't captured
+    let action = match rebind_state.action {
+        Some(_) if rebind_state.is_changed() && !rebind_state.escape_pending => {
+            *waited = 0.0;
+            return;
+        }
+        Some(action) => action,
+        None => return,
+    };
+
+    *waited += time.delta_seconds();
+    if *waited >= REBIND_TIMEOUT {
+        rebind_state.action = None;
+        rebind_state.escape_pending = false;
+        rebind_state.message = Some(format!("{} was left unchanged", action.name()));
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) && !rebind_state.escape_pending {
+        rebind_state.escape_pending = true;
+        *waited = 0.0;
+        return;
+    }
+
+    let binding = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepad_input
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.1))
+        });
+    let binding = match binding {
+        Some(binding) => binding,
+        None => return,
+    };
+
+    rebind_state.action = None;
+    rebind_state.escape_pending = false;
+    match input_map.rebind(action, binding) {
+        Ok(()) => {
+            input_map.save();
+            rebind_state.message = None;
+        }
+        Err(other) => {
+            rebind_state.message = Some(format!(
+                "{} is already bound to {}",
+                binding.name(),
+                other.name()
+            ));
+        }
+    }
+}
+
+fn highlight_selection(
+    selection: Res<MenuSelection>,
+    mut buttons: Query<(&MenuButton, &mut UiColor)>,
+) {
+    for (button, mut color) in buttons.iter_mut() {
+        let target = if button.index == selection.0 {
+            SELECTED_BUTTON
+        } else {
+            NORMAL_BUTTON
+        };
+        if color.0 != target {
+            color.0 = target;
+        }
+    }
+}
+
+fn return_to_menu(
+    mut game_state: ResMut<GameState>,
+    action_input: Res<ActionInput>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+) {
+    if action_input.just_pressed(InputAction::Menu)
+        && matches!(*game_state, GameState::Dead | GameState::LevelComplete)
+    {
+        *game_state = GameState::Menu;
+        game_reset_events.send(GameResetEvent);
+    }
+}
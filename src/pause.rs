@@ -0,0 +1,105 @@
+use bevy::{prelude::*, window::WindowFocused};
+use heron::PhysicsTime;
+
+use crate::{
+    controls::{ActionInput, ControlsLabel, InputAction, InputMap},
+    game_state::GameState,
+    ui::panel_line,
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        // Pausing happens before the game handles any input, so a paused frame never simulates
+        // anything
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            toggle_pause.label(PauseLabel).after(ControlsLabel),
+        )
+        .add_system(show_pause_overlay)
+        .add_system(hide_pause_overlay);
+    }
+}
+
+/// Pauses and resumes the game, before anything reacts to the frame's input.
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct PauseLabel;
+
+#[derive(Component)]
+struct PauseOverlay;
+
+fn toggle_pause(
+    mut game_state: ResMut<GameState>,
+    mut physics_time: ResMut<PhysicsTime>,
+    action_input: Res<ActionInput>,
+    mut window_focused_events: EventReader<WindowFocused>,
+) {
+    let toggle = action_input.just_pressed(InputAction::Pause);
+    let focus_lost = window_focused_events.iter().any(|event| !event.focused);
+
+    match *game_state {
+        GameState::Playing if toggle || focus_lost => {
+            *game_state = GameState::Paused;
+            physics_time.pause();
+        }
+        GameState::Paused if toggle => {
+            *game_state = GameState::Playing;
+            physics_time.resume();
+        }
+        _ => {}
+    }
+}
+
+fn show_pause_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<GameState>,
+    input_map: Res<InputMap>,
+    overlay: Query<(), With<PauseOverlay>>,
+) {
+    if !matches!(*game_state, GameState::Paused) || !overlay.is_empty() {
+        return;
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+            ..Default::default()
+        })
+        .insert(PauseOverlay)
+        .with_children(|parent| {
+            parent.spawn_bundle(panel_line("Paused".to_string(), &font, 72.0, Color::WHITE));
+            parent.spawn_bundle(panel_line(
+                format!(
+                    "Press {} to resume",
+                    input_map.describe_primary(InputAction::Pause)
+                ),
+                &font,
+                28.0,
+                Color::WHITE,
+            ));
+        });
+}
+
+fn hide_pause_overlay(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    overlay: Query<Entity, With<PauseOverlay>>,
+) {
+    if !matches!(*game_state, GameState::Paused) {
+        for entity in overlay.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
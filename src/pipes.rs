@@ -1,122 +1,473 @@
+use std::{cmp::Ordering, f32::consts::TAU};
+
 use bevy::prelude::*;
 use heron::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{game_state::GameState, GameResetEvent, Layer, WIN_HEIGHT, WIN_WIDTH};
-
-const PIPE_SPACING_X: f32 = (WIN_WIDTH / 5.0) + (PIPE_WIDTH / 5.0);
-const PIPE_SPACING_Y: f32 = 140.0;
-const PIPE_WIDTH: f32 = 80.0;
-const PIPE_HEIGHT: f32 = WIN_HEIGHT - PIPE_WIDTH / 2.0;
-const PIPE_PADDING: f32 = 160.0;
-const PIPE_SPEED: f32 = 100.0;
+use crate::{
+    camera::Playfield,
+    config::GameConfig,
+    difficulty::{Difficulty, DifficultyLabel},
+    game_state::GameState,
+    level::CurrentLevel,
+    simulation::{GameRng, Timestep},
+    theme::Theme,
+    GameResetEvent, Layer,
+};
 
 pub struct PipesPlugin;
 
 impl Plugin for PipesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_pipes)
-            .add_system(move_pipes)
-            .add_system(handle_game_reset);
+        app.init_resource::<PipePool>()
+            .add_event::<RespawnPipesEvent>()
+            .add_startup_system(spawn_pipes)
+            .add_system(move_pipes.after(DifficultyLabel))
+            .add_system(handle_game_reset)
+            .add_system(apply_pipe_theme);
+    }
+}
+
+/// Sent to lay the pipes out again for the [`CurrentLevel`] without resetting the rest of the
+/// run, such as the bird and the score.
+pub struct RespawnPipesEvent;
+
+/// A part of a pipe pair, positioned relative to the centre of the gap.
+#[derive(Component, Clone, Copy)]
+enum Pipe {
+    Top,
+    Bottom,
+    Gap,
+}
+
+impl Pipe {
+    /// Offset from the centre of the gap and half size of this part for a gap of `pipe_gap`.
+    fn layout(self, config: &GameConfig, pipe_gap: f32) -> (f32, Vec2) {
+        let pipe_height = config.pipe_height();
+        match self {
+            Pipe::Top => (
+                pipe_height / 2.0 + pipe_gap / 2.0,
+                Vec2::new(config.pipe_width / 2.0, pipe_height / 2.0),
+            ),
+            Pipe::Bottom => (
+                -pipe_height / 2.0 - pipe_gap / 2.0,
+                Vec2::new(config.pipe_width / 2.0, pipe_height / 2.0),
+            ),
+            Pipe::Gap => (
+                0.0,
+                Vec2::new(config.pipe_width / 2.0 - 0.5, pipe_gap / 2.0 - 0.5),
+            ),
+        }
     }
 }
 
 #[derive(Component)]
-struct Pipe;
+pub struct PipePair;
 
+/// Position of a pipe pair along the course. It's drawn at this minus the distance scrolled, so
+/// pairs end up in exactly the same place whenever they were placed.
 #[derive(Component)]
-struct PipePair;
+struct CoursePosition(f32);
 
-fn spawn_pipes(mut commands: Commands, asset_server: Res<AssetServer>) {
-    for i in 0..5 {
-        if i == 0 {
-            spawn_pipe(&mut commands, &asset_server, 0.0, 0.0);
-        } else {
-            let y = rand_y_pos();
-            spawn_pipe(&mut commands, &asset_server, i as f32 * PIPE_SPACING_X, y);
+/// The behaviours a pipe pair can be spawned with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PipeKind {
+    Static,
+    Oscillating,
+    OpenClose,
+    Drifting,
+}
+
+impl Default for PipeKind {
+    fn default() -> Self {
+        PipeKind::Static
+    }
+}
+
+/// The line a level ends at, placed after its last pipe pair.
+#[derive(Component)]
+pub struct FinishLine;
+
+/// Pipe pairs, with everything that changes when they're placed and moved.
+type PipePairs<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut PipeMotion,
+        &'static mut CoursePosition,
+        &'static Children,
+    ),
+    With<PipePair>,
+>;
+
+/// The pipes and gap sensors making up pipe pairs.
+type PipeParts<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Pipe,
+        &'static mut Transform,
+        &'static mut CollisionShape,
+        Option<&'static mut Sprite>,
+    ),
+    Without<PipePair>,
+>;
+
+type FinishLines<'w, 's> =
+    Query<'w, 's, &'static mut Transform, (With<FinishLine>, Without<PipePair>, Without<Pipe>)>;
+
+/// Where pipe pairs are kept while they're not part of the course.
+const PARKED_X: f32 = -100_000.0;
+
+/// Pipe pairs in use, and pairs that scrolled away and can be reused instead of spawning more.
+#[derive(Default)]
+struct PipePool {
+    active: Vec<Entity>,
+    free: Vec<Entity>,
+    /// Course position of the next pair to place in the endless mode.
+    next_x: f32,
+    /// Distance the course has scrolled since the last reset.
+    scrolled: f32,
+    /// Number of pairs placed since the last reset.
+    placed: usize,
+}
+
+/// How a pipe pair's gap moves while it scrolls past.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+enum PipeMotion {
+    Static,
+    /// The gap bobs up and down around `base_y` on a sine wave.
+    Oscillating {
+        base_y: f32,
+        phase: f32,
+    },
+    /// The gap repeatedly narrows from its full height `gap` and widens again.
+    OpenClose {
+        gap: f32,
+        phase: f32,
+    },
+    /// The gap moves at a constant speed, bouncing off the edges of the playable height.
+    Drifting {
+        velocity: f32,
+    },
+}
+
+fn spawn_pipes(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    theme: Res<Theme>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+    current_level: Res<CurrentLevel>,
+    mut pool: ResMut<PipePool>,
+    playfield: Option<Res<Playfield>>,
+    mut pipes: PipePairs,
+    mut parts: PipeParts,
+    mut finish_lines: FinishLines,
+) {
+    // Pipe pairs are moved out of the way rather than despawned, to be reused by the new course
+    for entity in std::mem::take(&mut pool.active) {
+        match pipes.get_mut(entity) {
+            Ok((mut transform, mut motion, _, _)) => {
+                transform.translation.x = PARKED_X;
+                *motion = PipeMotion::Static;
+                pool.free.push(entity);
+            }
+            // Spawned this frame, so it can't be moved until it exists, or reused before then
+            Err(_) => commands.entity(entity).despawn_recursive(),
+        }
+    }
+    pool.next_x = config.first_pipe_x;
+    pool.scrolled = 0.0;
+    pool.placed = 0;
+    let pipe_texture = pipe_texture(asset_server.as_deref(), &theme);
+
+    let level = match &current_level.level {
+        Some(level) => level,
+        None => {
+            for mut transform in finish_lines.iter_mut() {
+                transform.translation.x = PARKED_X;
+            }
+            fill_visible_width(
+                &mut commands,
+                pipe_texture.as_ref(),
+                &config,
+                &mut rng,
+                &mut pool,
+                visible_half_width(playfield.as_deref(), &config),
+                &mut pipes,
+                &mut parts,
+            );
+            return;
         }
+    };
+
+    let mut x = config.first_pipe_x;
+    for pipe in &level.pipes {
+        x += pipe.spacing;
+        let pipe_gap = pipe.gap.unwrap_or(config.pipe_gap);
+        let y = gap_offset(&config, pipe.y);
+        let motion = PipeMotion::new(pipe.kind, &mut rng, &config, y, pipe_gap);
+        place_pipe(
+            &mut commands,
+            pipe_texture.as_ref(),
+            &config,
+            &mut pool,
+            &mut pipes,
+            &mut parts,
+            Vec2::new(x, y),
+            pipe_gap,
+            motion,
+        );
+    }
+
+    let finish_x = x + level.finish;
+    if finish_lines.is_empty() {
+        spawn_finish_line(&mut commands, asset_server.as_deref(), &config, finish_x);
+    }
+    for mut transform in finish_lines.iter_mut() {
+        transform.translation.x = finish_x;
     }
 }
 
-fn rand_y_pos() -> f32 {
-    rand::thread_rng().gen_range(-1.0..1.0)
+/// Half the width of the visible playfield, or of the logical playfield when headless.
+fn visible_half_width(playfield: Option<&Playfield>, config: &GameConfig) -> f32 {
+    playfield
+        .map(|playfield| playfield.visible.x / 2.0)
+        .filter(|half_width| *half_width > 0.0)
+        .unwrap_or(config.window_width / 2.0)
 }
 
-fn spawn_pipe(commands: &mut Commands, asset_server: &AssetServer, mut x: f32, y: f32) {
-    let offset = (WIN_HEIGHT - PIPE_PADDING * 2.0) * (y / 2.0);
-    x += 300.0;
+/// Right edge of the logical playfield plus half a pipe, which pipe pairs wait beyond.
+fn playfield_edge(config: &GameConfig) -> f32 {
+    config.window_width / 2.0 + config.pipe_width / 2.0
+}
+
+/// Places endless pipe pairs up to just beyond the right edge of the view.
+///
+/// Pairs are generated in the same order whatever the width of the view, so a seed always gives
+/// the same course.
+fn fill_visible_width(
+    commands: &mut Commands,
+    pipe_texture: Option<&Handle<Image>>,
+    config: &GameConfig,
+    rng: &mut GameRng,
+    pool: &mut PipePool,
+    half_width: f32,
+    pipes: &mut PipePairs,
+    parts: &mut PipeParts,
+) {
+    while pool.next_x - pool.scrolled < half_width + config.pipe_width / 2.0 {
+        // Each pair follows the difficulty of the score it's reached at, rather than the score
+        // when it's placed, which depends on how far ahead the window shows
+        let difficulty = Difficulty::at(&config.difficulty, pool.placed as u32);
+        // The gap narrows with the difficulty, and the config may have been reloaded
+        let pipe_gap = config.pipe_gap * difficulty.gap;
+        // The first pair is always centred and static, to give the player a moment to start
+        let (y, motion) = if pool.placed == 0 {
+            (0.0, PipeMotion::Static)
+        } else {
+            let y = gap_offset(config, rand_y_pos(rng, difficulty.variance));
+            let kind = rand_kind(rng, config);
+            (y, PipeMotion::new(kind, rng, config, y, pipe_gap))
+        };
+
+        let position = Vec2::new(pool.next_x, y);
+        place_pipe(
+            commands,
+            pipe_texture,
+            config,
+            pool,
+            pipes,
+            parts,
+            position,
+            pipe_gap,
+            motion,
+        );
+        pool.next_x += config.pipe_spacing;
+    }
+}
+
+/// Places a pipe pair with its gap centred on `position` along the course, reusing a free pair if
+/// there is one.
+fn place_pipe(
+    commands: &mut Commands,
+    pipe_texture: Option<&Handle<Image>>,
+    config: &GameConfig,
+    pool: &mut PipePool,
+    pipes: &mut PipePairs,
+    parts: &mut PipeParts,
+    position: Vec2,
+    pipe_gap: f32,
+    motion: PipeMotion,
+) {
+    pool.placed += 1;
+    let scrolled = pool.scrolled;
+
+    let reused = pool.free.pop().and_then(|entity| {
+        let (mut transform, mut pair_motion, mut course_position, children) =
+            pipes.get_mut(entity).ok()?;
+        transform.translation.x = position.x - scrolled;
+        transform.translation.y = position.y;
+        *pair_motion = motion;
+        course_position.0 = position.x;
+        resize_pipe_pair(config, pipe_gap, children, parts);
+        Some(entity)
+    });
+
+    let entity = reused.unwrap_or_else(|| {
+        spawn_pipe(
+            commands,
+            pipe_texture,
+            config,
+            pipe_gap,
+            motion,
+            position,
+            scrolled,
+        )
+    });
+    pool.active.push(entity);
+}
+
+fn spawn_finish_line(
+    commands: &mut Commands,
+    asset_server: Option<&AssetServer>,
+    config: &GameConfig,
+    x: f32,
+) {
+    let transform = Transform::from_xyz(x, 0.0, 0.0);
+    let mut finish = match asset_server {
+        Some(_) => commands.spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+                custom_size: Some(Vec2::new(8.0, config.window_height)),
+                ..Default::default()
+            },
+            transform,
+            ..Default::default()
+        }),
+        None => commands.spawn_bundle((transform, GlobalTransform::default())),
+    };
+    finish.insert(FinishLine);
+}
 
+/// Returns the position of the centre of the next pipe gap the bird at `bird_x` has to pass.
+pub fn next_pipe_gap<'a>(
+    bird_x: f32,
+    pipe_width: f32,
+    pipe_pairs: impl IntoIterator<Item = &'a Transform>,
+) -> Option<Vec2> {
+    pipe_pairs
+        .into_iter()
+        .map(|transform| transform.translation.truncate())
+        .filter(|gap| gap.x + pipe_width / 2.0 >= bird_x)
+        .min_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal))
+}
+
+/// Random gap position between `-variance` and `variance`.
+fn rand_y_pos(rng: &mut GameRng, variance: f32) -> f32 {
+    rng.gen_range(-1.0..1.0) * variance
+}
+
+/// Picks the kind of a new pipe pair, weighted by the configured variants.
+fn rand_kind(rng: &mut GameRng, config: &GameConfig) -> PipeKind {
+    let weights = config.pipe_variants.weights();
+    let mut choice = rng.gen_range(0.0..weights.iter().sum::<f32>());
+    let index = weights
+        .iter()
+        .position(|weight| {
+            if choice < *weight {
+                true
+            } else {
+                choice -= weight;
+                false
+            }
+        })
+        .unwrap_or(0);
+
+    match index {
+        1 => PipeKind::Oscillating,
+        2 => PipeKind::OpenClose,
+        3 => PipeKind::Drifting,
+        _ => PipeKind::Static,
+    }
+}
+
+impl PipeMotion {
+    /// Starting motion of a pipe pair of `kind` with its gap at `y`.
+    fn new(kind: PipeKind, rng: &mut GameRng, config: &GameConfig, y: f32, pipe_gap: f32) -> Self {
+        match kind {
+            PipeKind::Static => PipeMotion::Static,
+            PipeKind::Oscillating => PipeMotion::Oscillating {
+                base_y: y,
+                phase: 0.0,
+            },
+            PipeKind::OpenClose => PipeMotion::OpenClose {
+                gap: pipe_gap,
+                phase: 0.0,
+            },
+            PipeKind::Drifting => {
+                let speed = config.pipe_variants.drift_speed;
+                PipeMotion::Drifting {
+                    velocity: if rng.gen() { speed } else { -speed },
+                }
+            }
+        }
+    }
+}
+
+/// Spawns a pipe pair at `position` along the course, attaching sprites only when there is a
+/// texture for them.
+fn spawn_pipe(
+    commands: &mut Commands,
+    pipe_texture: Option<&Handle<Image>>,
+    config: &GameConfig,
+    pipe_gap: f32,
+    motion: PipeMotion,
+    position: Vec2,
+    scrolled: f32,
+) -> Entity {
     commands
         .spawn()
-        .insert(Transform::from_xyz(x, offset, 0.0))
+        .insert(Transform::from_xyz(position.x - scrolled, position.y, 0.0))
         .insert(GlobalTransform::default())
         .insert(PipePair)
+        .insert(CoursePosition(position.x))
+        .insert(motion)
         .with_children(|commands| {
-            // Top pipe
-            commands
-                .spawn_bundle(SpriteBundle {
-                    texture: asset_server.load("sprites/pipe.png"),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(PIPE_WIDTH, PIPE_HEIGHT)),
-                        flip_y: true,
-                        ..Default::default()
-                    },
-                    transform: Transform::from_xyz(
-                        0.0,
-                        PIPE_HEIGHT / 2.0 + PIPE_SPACING_Y / 2.0,
-                        0.0,
-                    ),
-                    ..Default::default()
-                })
-                .insert(RigidBody::KinematicPositionBased)
-                .insert(CollisionShape::Cuboid {
-                    half_extends: Vec3::new(PIPE_WIDTH / 2.0, PIPE_HEIGHT / 2.0, 0.0),
-                    border_radius: None,
-                })
-                .insert(
-                    CollisionLayers::none()
-                        .with_group(Layer::World)
-                        .with_mask(Layer::Player),
-                )
-                .insert(Pipe);
-
-            // Bottom pipe
-            commands
-                .spawn_bundle(SpriteBundle {
-                    texture: asset_server.load("sprites/pipe.png"),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(PIPE_WIDTH, PIPE_HEIGHT)),
-                        ..Default::default()
-                    },
-                    transform: Transform::from_xyz(
-                        0.0,
-                        ((PIPE_HEIGHT / 2.0) * -1.0) - PIPE_SPACING_Y / 2.0,
-                        0.0,
-                    ),
-                    ..Default::default()
-                })
-                .insert(RigidBody::KinematicPositionBased)
-                .insert(CollisionShape::Cuboid {
-                    half_extends: Vec3::new(PIPE_WIDTH / 2.0, PIPE_HEIGHT / 2.0, 0.0),
-                    border_radius: None,
-                })
-                .insert(
-                    CollisionLayers::none()
-                        .with_group(Layer::World)
-                        .with_mask(Layer::Player),
-                )
-                .insert(Pipe);
+            for part in [Pipe::Top, Pipe::Bottom] {
+                let (y, half_extends) = part.layout(config, pipe_gap);
+                let mut pipe = commands
+                    .spawn_bundle((Transform::from_xyz(0.0, y, 0.0), GlobalTransform::default()));
+                if let Some(pipe_texture) = pipe_texture {
+                    pipe.insert_bundle(pipe_sprite(
+                        pipe_texture.clone(),
+                        half_extends * 2.0,
+                        matches!(part, Pipe::Top),
+                    ));
+                }
+                pipe.insert(RigidBody::KinematicPositionBased)
+                    .insert(CollisionShape::Cuboid {
+                        half_extends: half_extends.extend(0.0),
+                        border_radius: None,
+                    })
+                    .insert(
+                        CollisionLayers::none()
+                            .with_group(Layer::World)
+                            .with_mask(Layer::Player),
+                    )
+                    .insert(part);
+            }
 
             // Pipe gap sensor
+            let (_, half_extends) = Pipe::Gap.layout(config, pipe_gap);
             commands
                 .spawn_bundle((Transform::default(), GlobalTransform::default()))
                 .insert(RigidBody::Sensor)
                 .insert(CollisionShape::Cuboid {
-                    half_extends: Vec3::new(
-                        PIPE_WIDTH / 2.0 - 0.5,
-                        PIPE_SPACING_Y / 2.0 - 0.5,
-                        0.0,
-                    ),
+                    half_extends: half_extends.extend(0.0),
                     border_radius: None,
                 })
                 .insert(
@@ -124,48 +475,256 @@ fn spawn_pipe(commands: &mut Commands, asset_server: &AssetServer, mut x: f32, y
                         .with_group(Layer::PipeGap)
                         .with_mask(Layer::Player),
                 )
-                .insert(Pipe);
-        });
+                .insert(Pipe::Gap);
+        })
+        .id()
+}
+
+/// Height of a gap's centre for a position `y` between -1 and 1.
+pub fn gap_offset(config: &GameConfig, y: f32) -> f32 {
+    (config.window_height - config.pipe_padding * 2.0) * (y / 2.0)
+}
+
+/// The active theme's pipe texture, or `None` in headless apps, which have no sprites.
+fn pipe_texture(asset_server: Option<&AssetServer>, theme: &Theme) -> Option<Handle<Image>> {
+    asset_server.map(|asset_server| asset_server.load(theme.world.pipe.as_str()))
+}
+
+fn pipe_sprite(
+    texture: Handle<Image>,
+    size: Vec2,
+    flip_y: bool,
+) -> (Sprite, Handle<Image>, Visibility) {
+    (
+        Sprite {
+            custom_size: Some(size),
+            flip_y,
+            ..Default::default()
+        },
+        texture,
+        Visibility::default(),
+    )
+}
+
+/// Switches the pipes already spawned to a newly chosen theme.
+fn apply_pipe_theme(
+    asset_server: Option<Res<AssetServer>>,
+    theme: Res<Theme>,
+    mut textures: Query<&mut Handle<Image>, With<Pipe>>,
+) {
+    if !theme.is_changed() || theme.is_added() {
+        return;
+    }
+
+    if let Some(texture) = pipe_texture(asset_server.as_deref(), &theme) {
+        for mut pipe_texture in textures.iter_mut() {
+            if *pipe_texture != texture {
+                *pipe_texture = texture.clone();
+            }
+        }
+    }
 }
 
 fn move_pipes(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    theme: Res<Theme>,
     time: Res<Time>,
-    mut pipes: Query<&mut Transform, With<PipePair>>,
+    timestep: Res<Timestep>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<GameRng>,
+    current_level: Res<CurrentLevel>,
+    mut pool: ResMut<PipePool>,
+    playfield: Option<Res<Playfield>>,
+    mut pipes: PipePairs,
+    mut parts: PipeParts,
+    mut finish_lines: FinishLines,
     game_state: Res<GameState>,
 ) {
-    if matches!(*game_state, GameState::Playing) {
-        for mut pipe in pipes.iter_mut() {
-            pipe.translation.x -= PIPE_SPEED * time.delta_seconds();
+    if !matches!(*game_state, GameState::Playing) {
+        return;
+    }
 
-            if pipe.translation.x < (WIN_WIDTH / 2.0 + PIPE_WIDTH / 2.0) * -1.0 {
-                pipe.translation.x = WIN_WIDTH / 2.0 + PIPE_WIDTH / 2.0;
+    let delta = timestep.delta_seconds(&time);
+    let distance = config.pipe_speed * difficulty.speed * delta;
+    for mut finish_line in finish_lines.iter_mut() {
+        finish_line.translation.x -= distance;
+    }
+    pool.scrolled += distance;
+
+    let half_width = visible_half_width(playfield.as_deref(), &config);
+    let edge = playfield_edge(&config);
+    // Levels are a finite course, so only the endless mode recycles pipes
+    let endless = current_level.level.is_none();
+    let pool = &mut *pool;
+    let mut index = 0;
+    while index < pool.active.len() {
+        let entity = pool.active[index];
+        let (mut pipe, mut motion, course_position, children) = match pipes.get_mut(entity) {
+            Ok(pipe) => pipe,
+            // Spawned this frame, already in place
+            Err(_) => {
+                index += 1;
+                continue;
+            }
+        };
+        // Pairs beyond the logical playfield, only visible in wider windows, hold their motion
+        // until they reach it, so they move the same however wide the window is
+        let in_playfield = pipe.translation.x < edge;
+        pipe.translation.x = course_position.0 - pool.scrolled;
+
+        if endless && pipe.translation.x < -half_width - config.pipe_width / 2.0 {
+            pipe.translation.x = PARKED_X;
+            *motion = PipeMotion::Static;
+            pool.free.push(pool.active.swap_remove(index));
+            continue;
+        }
 
-                let pos = rand_y_pos();
-                let offset = (WIN_HEIGHT - PIPE_PADDING * 2.0) * (pos / 2.0);
-                pipe.translation.y = offset;
+        if in_playfield {
+            if let Some(pipe_gap) = animate_pipe(&config, delta, &mut pipe, &mut motion) {
+                move_pipes_apart(&config, pipe_gap, children, &mut parts);
             }
         }
+        index += 1;
+    }
+
+    if endless {
+        fill_visible_width(
+            &mut commands,
+            pipe_texture(asset_server.as_deref(), &theme).as_ref(),
+            &config,
+            &mut rng,
+            pool,
+            half_width,
+            &mut pipes,
+            &mut parts,
+        );
     }
 }
 
-fn handle_game_reset(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut game_reset_events: EventReader<GameResetEvent>,
-    mut pipes: QuerySet<(
-        QueryState<Entity, With<Pipe>>,
-        QueryState<Entity, With<PipePair>>,
-    )>,
+/// Advances a pipe pair's motion by `delta` seconds, returning the new gap height if it changed.
+///
+/// The pipes and gap sensor are children of the pair, so moving the pair moves them all.
+fn animate_pipe(
+    config: &GameConfig,
+    delta: f32,
+    transform: &mut Transform,
+    motion: &mut PipeMotion,
+) -> Option<f32> {
+    let variants = &config.pipe_variants;
+    let limit = gap_offset(config, 1.0);
+    match motion {
+        PipeMotion::Static => None,
+        PipeMotion::Oscillating { base_y, phase } => {
+            *phase += delta * variants.oscillation_frequency * TAU;
+            transform.translation.y =
+                (*base_y + variants.oscillation_amplitude * phase.sin()).clamp(-limit, limit);
+            None
+        }
+        PipeMotion::OpenClose { gap, phase } => {
+            *phase += delta * variants.open_close_frequency * TAU;
+            // Starts fully open, so the gap never snaps shut as the pair appears
+            let closed = (1.0 - phase.cos()) / 2.0;
+            Some(*gap * (1.0 - variants.close_fraction * closed))
+        }
+        PipeMotion::Drifting { velocity } => {
+            transform.translation.y += *velocity * delta;
+            if transform.translation.y.abs() > limit {
+                transform.translation.y = transform.translation.y.clamp(-limit, limit);
+                *velocity = -*velocity;
+            }
+            None
+        }
+    }
+}
+
+/// Moves the top and bottom pipes of a pair to leave a gap of `pipe_gap` between them.
+///
+/// Only their transforms change, as changing a collider's shape rebuilds it in the physics. The
+/// gap sensor keeps the size of the fully open gap, which the closing pipes overlap.
+fn move_pipes_apart(
+    config: &GameConfig,
+    pipe_gap: f32,
+    children: &Children,
+    parts: &mut PipeParts,
 ) {
-    if game_reset_events.iter().next().is_some() {
-        let q0 = pipes.q0();
-        for entity in q0.iter() {
-            commands.entity(entity).despawn();
+    for child in children.iter() {
+        if let Ok((part, mut transform, _, _)) = parts.get_mut(*child) {
+            if !matches!(part, Pipe::Gap) {
+                transform.translation.y = part.layout(config, pipe_gap).0;
+            }
         }
-        let q1 = pipes.q1();
-        for entity in q1.iter() {
-            commands.entity(entity).despawn();
+    }
+}
+
+/// Moves and resizes the parts of a pair being placed to fit a gap of `pipe_gap`.
+fn resize_pipe_pair(
+    config: &GameConfig,
+    pipe_gap: f32,
+    children: &Children,
+    parts: &mut PipeParts,
+) {
+    for child in children.iter() {
+        if let Ok((part, mut transform, mut shape, sprite)) = parts.get_mut(*child) {
+            resize_pipe_part(config, pipe_gap, *part, &mut transform, &mut shape, sprite);
         }
-        spawn_pipes(commands, asset_server);
+    }
+}
+
+/// Moves and resizes a part of a pipe pair to fit a gap of `pipe_gap`.
+fn resize_pipe_part(
+    config: &GameConfig,
+    pipe_gap: f32,
+    part: Pipe,
+    transform: &mut Transform,
+    shape: &mut CollisionShape,
+    sprite: Option<Mut<Sprite>>,
+) {
+    let (y, half_extends) = part.layout(config, pipe_gap);
+    transform.translation.y = y;
+    *shape = CollisionShape::Cuboid {
+        half_extends: half_extends.extend(0.0),
+        border_radius: None,
+    };
+    if let Some(mut sprite) = sprite {
+        sprite.custom_size = Some(half_extends * 2.0);
+    }
+}
+
+fn handle_game_reset(
+    commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    theme: Res<Theme>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+    current_level: Res<CurrentLevel>,
+    pool: ResMut<PipePool>,
+    mut game_reset_events: EventReader<GameResetEvent>,
+    mut respawn_pipes_events: EventReader<RespawnPipesEvent>,
+    playfield: Option<Res<Playfield>>,
+    pipes: PipePairs,
+    parts: PipeParts,
+    finish_lines: FinishLines,
+) {
+    let reset = game_reset_events.iter().next().is_some();
+    let respawn = respawn_pipes_events.iter().next().is_some();
+    if reset {
+        rng.new_course();
+    }
+    if reset || respawn {
+        spawn_pipes(
+            commands,
+            asset_server,
+            theme,
+            config,
+            rng,
+            current_level,
+            pool,
+            playfield,
+            pipes,
+            parts,
+            finish_lines,
+        );
     }
 }
@@ -0,0 +1,284 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    autopilot::Autopilot,
+    high_scores::HighScores,
+    level::LevelCompleteEvent,
+    replay::ReplayPlayback,
+    storage,
+    theme::{BirdSkin, ThemeManifest, WorldTheme},
+    DiedEvent, FlapEvent,
+};
+
+/// Counts the player's lifetime totals and unlocks cosmetics as they reach their milestones,
+/// saving both between sessions.
+///
+/// Score milestones are read from the [`HighScores`], so that plugin has to be added too.
+pub struct ProgressPlugin;
+
+impl Plugin for ProgressPlugin {
+    fn build(&self, app: &mut App) {
+        let path = storage::data_path("progress.ron");
+        let progress: Progress = path
+            .as_deref()
+            .map(storage::load_or_default)
+            .unwrap_or_default();
+
+        app.insert_resource(progress)
+            .insert_resource(ProgressPath(path))
+            .add_system(count_totals.label(ProgressLabel))
+            .add_system(unlock_reached.after(ProgressLabel));
+    }
+}
+
+/// Systems that count the totals in [`Progress`].
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct ProgressLabel;
+
+/// A milestone that unlocks a bird skin or world theme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unlock {
+    /// Reaching a score in a single ranked run.
+    Score(u32),
+    /// Flapping a number of times over all runs.
+    Flaps(u64),
+}
+
+impl Unlock {
+    /// Describes what unlocks the item, such as "Get a high score of 10".
+    pub fn describe(&self) -> String {
+        match self {
+            Unlock::Score(score) => format!("Get a high score of {}", score),
+            Unlock::Flaps(flaps) => format!("Flap {} times", flaps),
+        }
+    }
+
+    pub fn reached(&self, high_scores: &HighScores, progress: &Progress) -> bool {
+        match *self {
+            Unlock::Score(score) => high_scores.best().unwrap_or(0) >= score,
+            Unlock::Flaps(flaps) => progress.flaps >= flaps,
+        }
+    }
+}
+
+/// The cosmetics unlocked so far, and the totals counted over all sessions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    /// Names of the unlocked bird skins, which stay unlocked if their milestones change.
+    pub birds: BTreeSet<String>,
+    /// Names of the unlocked world themes.
+    pub worlds: BTreeSet<String>,
+    /// Runs finished, whether by dying or completing a level.
+    pub runs: u32,
+    pub flaps: u64,
+}
+
+impl Progress {
+    pub fn bird_unlocked(&self, bird: &BirdSkin) -> bool {
+        bird.unlock.is_none() || self.birds.contains(&bird.name)
+    }
+
+    pub fn world_unlocked(&self, world: &WorldTheme) -> bool {
+        world.unlock.is_none() || self.worlds.contains(&world.name)
+    }
+
+    /// Unlocks the items whose milestones have been reached, returning whether there were any.
+    fn unlock_reached(&mut self, manifest: &ThemeManifest, high_scores: &HighScores) -> bool {
+        let reached = |unlock: Option<Unlock>| {
+            unlock.map_or(false, |unlock| unlock.reached(high_scores, self))
+        };
+        let birds: Vec<_> = manifest
+            .birds
+            .iter()
+            .filter(|bird| !self.bird_unlocked(bird))
+            .filter(|bird| reached(bird.unlock))
+            .map(|bird| bird.name.clone())
+            .collect();
+        let worlds: Vec<_> = manifest
+            .worlds
+            .iter()
+            .filter(|world| !self.world_unlocked(world))
+            .filter(|world| reached(world.unlock))
+            .map(|world| world.name.clone())
+            .collect();
+
+        for name in &birds {
+            info!("unlocked the {} bird", name);
+        }
+        for name in &worlds {
+            info!("unlocked the {} theme", name);
+        }
+        let unlocked = !birds.is_empty() || !worlds.is_empty();
+        self.birds.extend(birds);
+        self.worlds.extend(worlds);
+        unlocked
+    }
+}
+
+/// Where the progress is saved, if the platform has a data directory.
+struct ProgressPath(Option<PathBuf>);
+
+impl ProgressPath {
+    fn save(&self, progress: &Progress) {
+        if let Some(path) = &self.0 {
+            if let Err(err) = storage::save(path, progress) {
+                error!("failed to save progress: {}", err);
+            }
+        }
+    }
+}
+
+/// Counts the flaps and finished runs of the player, saving the totals at the end of each run
+/// rather than on every flap.
+fn count_totals(
+    mut flap_events: EventReader<FlapEvent>,
+    mut died_events: EventReader<DiedEvent>,
+    mut level_complete_events: EventReader<LevelCompleteEvent>,
+    autopilot: Res<Autopilot>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut progress: ResMut<Progress>,
+    path: Res<ProgressPath>,
+) {
+    let flaps = flap_events.iter().count() as u64;
+    let died = died_events.iter().next().is_some();
+    let level_complete = level_complete_events.iter().next().is_some();
+    let run_over = died || level_complete;
+    // Totals have to be earned by the player, not the autopilot or a replay of a run that
+    // already counted
+    if autopilot.enabled || replay_playback.is_some() || (flaps == 0 && !run_over) {
+        return;
+    }
+
+    progress.flaps += flaps;
+    if run_over {
+        progress.runs += 1;
+        path.save(&progress);
+    }
+}
+
+/// Unlocks the items whose milestones have been reached as the scores and totals change, saving
+/// straight away so they're kept even if the game is closed mid-run.
+fn unlock_reached(
+    autopilot: Res<Autopilot>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    manifest: Res<ThemeManifest>,
+    high_scores: Res<HighScores>,
+    mut progress: ResMut<Progress>,
+    path: Res<ProgressPath>,
+) {
+    // Milestones have to be reached by the player, not the autopilot or a replay of a run that
+    // already counted
+    if autopilot.enabled || replay_playback.is_some() {
+        return;
+    }
+    if !high_scores.is_changed() && !progress.is_changed() && !manifest.is_changed() {
+        return;
+    }
+
+    if progress.unlock_reached(&manifest, &high_scores) {
+        path.save(&progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::high_scores::HighScore;
+
+    fn bird(name: &str, unlock: Option<Unlock>) -> BirdSkin {
+        BirdSkin {
+            name: name.to_string(),
+            unlock,
+            ..BirdSkin::default()
+        }
+    }
+
+    fn world(name: &str, unlock: Option<Unlock>) -> WorldTheme {
+        WorldTheme {
+            name: name.to_string(),
+            unlock,
+            ..WorldTheme::default()
+        }
+    }
+
+    fn manifest(bird_unlock: Option<Unlock>, world_unlock: Option<Unlock>) -> ThemeManifest {
+        ThemeManifest {
+            birds: vec![bird("Red", None), bird("Blue", bird_unlock)],
+            worlds: vec![world("Day", None), world("Night", world_unlock)],
+        }
+    }
+
+    fn high_scores(best: u32) -> HighScores {
+        HighScores {
+            entries: vec![HighScore {
+                score: best,
+                date: 0,
+                seed: 0,
+                duration: 0.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn items_without_an_unlock_are_always_available() {
+        let progress = Progress::default();
+        assert!(progress.bird_unlocked(&bird("Red", None)));
+        assert!(progress.world_unlocked(&world("Day", None)));
+        assert!(!progress.bird_unlocked(&bird("Blue", Some(Unlock::Score(0)))));
+
+        let mut progress = Progress::default();
+        assert!(!progress.unlock_reached(&manifest(None, None), &HighScores::default()));
+        assert_eq!(progress, Progress::default());
+    }
+
+    #[test]
+    fn score_milestones_unlock_at_exactly_the_score() {
+        let manifest = manifest(Some(Unlock::Score(10)), None);
+        let mut progress = Progress::default();
+
+        assert!(!progress.unlock_reached(&manifest, &high_scores(9)));
+        assert!(!progress.bird_unlocked(&manifest.birds[1]));
+        assert!(progress.unlock_reached(&manifest, &high_scores(10)));
+        assert!(progress.bird_unlocked(&manifest.birds[1]));
+    }
+
+    #[test]
+    fn flap_milestones_unlock_at_exactly_the_total() {
+        let manifest = manifest(None, Some(Unlock::Flaps(500)));
+        let mut progress = Progress {
+            flaps: 499,
+            ..Progress::default()
+        };
+
+        assert!(!progress.unlock_reached(&manifest, &HighScores::default()));
+        assert!(!progress.world_unlocked(&manifest.worlds[1]));
+        progress.flaps += 1;
+        assert!(progress.unlock_reached(&manifest, &HighScores::default()));
+        assert!(progress.world_unlocked(&manifest.worlds[1]));
+    }
+
+    #[test]
+    fn unlocked_items_stay_unlocked_when_their_milestone_changes() {
+        let mut progress = Progress::default();
+        assert!(progress.unlock_reached(&manifest(Some(Unlock::Score(10)), None), &high_scores(10)));
+
+        let harder = manifest(Some(Unlock::Score(50)), None);
+        assert!(!progress.unlock_reached(&harder, &high_scores(10)));
+        assert!(progress.bird_unlocked(&harder.birds[1]));
+    }
+
+    #[test]
+    fn nothing_new_is_reported_once_everything_reached_is_unlocked() {
+        let manifest = manifest(Some(Unlock::Score(10)), Some(Unlock::Score(25)));
+        let mut progress = Progress::default();
+
+        assert!(progress.unlock_reached(&manifest, &high_scores(30)));
+        assert!(!progress.unlock_reached(&manifest, &high_scores(30)));
+        assert_eq!(progress.birds.len(), 1);
+        assert_eq!(progress.worlds.len(), 1);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::VecDeque,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::{ActionInput, ControlsLabel, InputAction},
+    game_state::GameState,
+    pause::PauseLabel,
+    simulation::{GameRng, SimulationTick},
+    DiedEvent,
+};
+
+/// Version written to new replay files.
+pub const REPLAY_VERSION: u32 = 2;
+/// Oldest replay version that can still be played back.
+pub const MIN_REPLAY_VERSION: u32 = 1;
+
+/// A recorded session: the RNG seed and the tick of every flap and restart input.
+///
+/// Replays are only reproducible when recorded and played back with a fixed timestep.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub flaps: Vec<u64>,
+    /// Added in version 2.
+    #[serde(default)]
+    pub restarts: Vec<u64>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            seed,
+            flaps: Vec::new(),
+            restarts: Vec::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let contents = fs::read_to_string(path)?;
+        let replay: Replay = ron::de::from_str(&contents)?;
+        if !(MIN_REPLAY_VERSION..=REPLAY_VERSION).contains(&replay.version) {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let contents = ron::ser::to_string_pretty(self, Default::default())?;
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Format(ron::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{}", err),
+            ReplayError::Format(err) => write!(f, "invalid replay file: {}", err),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported replay version {} (expected {} to {})",
+                version, MIN_REPLAY_VERSION, REPLAY_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl From<ron::Error> for ReplayError {
+    fn from(err: ron::Error) -> Self {
+        ReplayError::Format(err)
+    }
+}
+
+pub enum ReplayPlugin {
+    /// Records every flap and restart, saving to the path whenever the bird dies or the app
+    /// exits.
+    Record(PathBuf),
+    /// Feeds the replay's inputs to the game instead of the player's.
+    Playback(Replay),
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match self {
+            ReplayPlugin::Record(path) => {
+                app.insert_resource(ReplayRecorder {
+                    path: path.clone(),
+                    flaps: Vec::new(),
+                    restarts: Vec::new(),
+                })
+                .add_system(record_inputs)
+                // After every system that could send an AppExit
+                .add_system_to_stage(CoreStage::Last, save_replay);
+            }
+            ReplayPlugin::Playback(replay) => {
+                app.insert_resource(GameRng::new(replay.seed))
+                    .insert_resource(ReplayPlayback {
+                        flaps: replay.flaps.iter().copied().collect(),
+                        restarts: replay.restarts.iter().copied().collect(),
+                    })
+                    .add_system_to_stage(
+                        CoreStage::PreUpdate,
+                        play_inputs.after(ControlsLabel).after(PauseLabel),
+                    );
+            }
+        }
+    }
+}
+
+struct ReplayRecorder {
+    path: PathBuf,
+    flaps: Vec<u64>,
+    restarts: Vec<u64>,
+}
+
+/// Ticks of the inputs still to be played, earliest first. Only present while playing back.
+pub struct ReplayPlayback {
+    flaps: VecDeque<u64>,
+    restarts: VecDeque<u64>,
+}
+
+fn record_inputs(
+    tick: Res<SimulationTick>,
+    action_input: Res<ActionInput>,
+    game_state: Res<GameState>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    // Paused frames don't advance the tick, so inputs during them are ignored by the game
+    if matches!(*game_state, GameState::Paused) {
+        return;
+    }
+
+    if action_input.just_pressed(InputAction::Flap) {
+        recorder.flaps.push(tick.0);
+    }
+    if action_input.just_pressed(InputAction::Restart) {
+        recorder.restarts.push(tick.0);
+    }
+}
+
+fn save_replay(
+    mut died_events: EventReader<DiedEvent>,
+    mut app_exit_events: EventReader<AppExit>,
+    recorder: Res<ReplayRecorder>,
+    rng: Res<GameRng>,
+) {
+    let died = died_events.iter().next().is_some();
+    let exited = app_exit_events.iter().next().is_some();
+    if died || exited {
+        let replay = Replay {
+            flaps: recorder.flaps.clone(),
+            restarts: recorder.restarts.clone(),
+            ..Replay::new(rng.seed())
+        };
+        match replay.save(&recorder.path) {
+            Ok(()) => info!("saved replay to {}", recorder.path.display()),
+            Err(err) => error!("failed to save replay: {}", err),
+        }
+    }
+}
+
+fn play_inputs(
+    tick: Res<SimulationTick>,
+    game_state: Res<GameState>,
+    mut action_input: ResMut<ActionInput>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    let playback = &mut *playback;
+    let mut played = false;
+    for (action, ticks) in [
+        (InputAction::Flap, &mut playback.flaps),
+        (InputAction::Restart, &mut playback.restarts),
+    ] {
+        action_input.release(action);
+        // Paused frames don't advance the tick, so the input waits for the game to be resumed,
+        // just as it wasn't recorded while paused
+        if matches!(*game_state, GameState::Paused) {
+            continue;
+        }
+        if ticks.front() == Some(&tick.0) {
+            ticks.pop_front();
+            action_input.press(action);
+            played = true;
+        }
+    }
+
+    if played && playback.flaps.is_empty() && playback.restarts.is_empty() {
+        info!("replay finished");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{self, temp_path};
+
+    fn load_str(name: &str, contents: &str) -> Result<Replay, ReplayError> {
+        storage::load_temp(name, contents, |path| Replay::load(path))
+    }
+
+    #[test]
+    fn saved_replays_load_back() {
+        let replay = Replay {
+            flaps: vec![3, 40, 41],
+            restarts: vec![90],
+            ..Replay::new(1234)
+        };
+        let path = temp_path("round-trip");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), replay);
+    }
+
+    #[test]
+    fn version_1_replays_load_without_restarts() {
+        let replay = load_str("version-1", "(version: 1, seed: 5, flaps: [10, 20])").unwrap();
+        assert_eq!(replay.flaps, vec![10, 20]);
+        assert!(replay.restarts.is_empty());
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        for version in [0, REPLAY_VERSION + 1] {
+            let contents = format!("(version: {}, seed: 5, flaps: [])", version);
+            let result = load_str(&format!("version-{}", version), &contents);
+            assert!(
+                matches!(result, Err(ReplayError::UnsupportedVersion(v)) if v == version),
+                "version {} was not rejected",
+                version
+            );
+        }
+    }
+}
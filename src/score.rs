@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{GameResetEvent, IncreaseScoreEvent};
+use crate::{camera::ScaledText, GameResetEvent, IncreaseScoreEvent};
 
 pub struct ScorePlugin;
 
@@ -13,9 +13,18 @@ impl Plugin for ScorePlugin {
 }
 
 #[derive(Component, Default)]
-struct Score(u32);
+pub struct Score(pub u32);
+
+fn setup_score(mut commands: Commands, asset_server: Option<Res<AssetServer>>) {
+    // Headless apps keep score without any UI to display it
+    let asset_server = match asset_server {
+        Some(asset_server) => asset_server,
+        None => {
+            commands.spawn().insert(Score::default());
+            return;
+        }
+    };
 
-fn setup_score(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn_bundle(UiCameraBundle::default());
 
     commands
@@ -53,29 +62,34 @@ fn setup_score(mut commands: Commands, asset_server: Res<AssetServer>) {
             },
             ..Default::default()
         })
+        .insert(ScaledText { font_size: 60.0 })
         .insert(Score::default());
 }
 
 fn handle_increase_score(
     mut increase_score_events: EventReader<IncreaseScoreEvent>,
-    mut score_text: Query<(&mut Text, &mut Score)>,
+    mut score_text: Query<(&mut Score, Option<&mut Text>)>,
 ) {
     for _ in increase_score_events.iter() {
-        for (mut text, mut score) in score_text.iter_mut() {
+        for (mut score, text) in score_text.iter_mut() {
             score.0 += 1;
-            text.sections[1].value = score.0.to_string();
+            if let Some(mut text) = text {
+                text.sections[1].value = score.0.to_string();
+            }
         }
     }
 }
 
 fn handle_game_reset(
     mut game_reset_events: EventReader<GameResetEvent>,
-    mut score_text: Query<(&mut Text, &mut Score)>,
+    mut score_text: Query<(&mut Score, Option<&mut Text>)>,
 ) {
     if game_reset_events.iter().next().is_some() {
-        for (mut text, mut score) in score_text.iter_mut() {
+        for (mut score, text) in score_text.iter_mut() {
             score.0 = 0;
-            text.sections[1].value = "0".to_string();
+            if let Some(mut text) = text {
+                text.sections[1].value = "0".to_string();
+            }
         }
     }
 }
@@ -0,0 +1,245 @@
+use std::time::{Duration, Instant};
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use heron::PhysicsSteps;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+use crate::game_state::{GameStartedEvent, GameState};
+
+/// Number of simulation ticks per second when running with a [`Timestep::Fixed`].
+pub const TICK_RATE: f32 = 60.0;
+
+/// Most ticks run in a single frame, so the game slows down rather than freezing while it
+/// catches up after a stall.
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timestep>()
+            .init_resource::<GameRng>()
+            .init_resource::<SimulationTick>()
+            .add_startup_system(configure_physics_steps)
+            .add_system(mark_course_started)
+            .add_system_to_stage(CoreStage::Last, advance_tick);
+    }
+}
+
+/// Runs the whole schedule once for every tick of real time that has passed, so a
+/// [`Timestep::Fixed`] game runs at [`TICK_RATE`] whatever the display's refresh rate.
+///
+/// Headless apps don't add this, and advance by exactly one tick per [`App::update`].
+pub struct TickRatePlugin;
+
+impl Plugin for TickRatePlugin {
+    fn build(&self, app: &mut App) {
+        app.schedule.set_run_criteria(run_due_ticks.system());
+    }
+}
+
+/// How far the simulation advances each frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestep {
+    /// Advance by the real time elapsed since the last frame.
+    Variable,
+    /// Advance by exactly one tick of `1 / TICK_RATE` seconds every update, making runs
+    /// reproducible regardless of frame rate. Windowed apps pace the updates with a
+    /// [`TickRatePlugin`].
+    Fixed,
+}
+
+impl Default for Timestep {
+    fn default() -> Self {
+        Timestep::Variable
+    }
+}
+
+impl Timestep {
+    pub fn delta_seconds(&self, time: &Time) -> f32 {
+        match self {
+            Timestep::Variable => time.delta_seconds(),
+            Timestep::Fixed => 1.0 / TICK_RATE,
+        }
+    }
+}
+
+/// Number of ticks simulated since the app started, not counting ticks spent paused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimulationTick(pub u64);
+
+/// Seedable random number generator used for all gameplay randomness.
+///
+/// Every course is generated from its own seed, the session's seed plus the number of runs played
+/// before it, so any run can be played again on its own with `--seed`.
+pub struct GameRng {
+    seed: u64,
+    /// Runs played since the generator was created or reseeded.
+    runs: u64,
+    /// Whether the current course has been played, rather than only generated.
+    started: bool,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            runs: 0,
+            started: false,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed this generator was created or last reseeded with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The seed the current course was generated from.
+    pub fn run_seed(&self) -> u64 {
+        self.seed.wrapping_add(self.runs)
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        *self = GameRng::new(seed);
+    }
+
+    /// Restarts the generator for a new course. Courses that were reset before being played,
+    /// like the one behind the menu, don't count as a run, so they're generated again.
+    ///
+    /// Called by whatever generates the course, right before it does, so the course always comes
+    /// from [`GameRng::run_seed`] however the systems sending the reset are ordered.
+    pub(crate) fn new_course(&mut self) {
+        if self.started {
+            self.runs += 1;
+            self.started = false;
+        }
+        self.rng = StdRng::seed_from_u64(self.run_seed());
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::random())
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+fn configure_physics_steps(mut commands: Commands, timestep: Res<Timestep>) {
+    if *timestep == Timestep::Fixed {
+        commands.insert_resource(PhysicsSteps::every_frame(Duration::from_secs_f32(
+            1.0 / TICK_RATE,
+        )));
+    }
+}
+
+fn mark_course_started(
+    mut game_started_events: EventReader<GameStartedEvent>,
+    mut rng: ResMut<GameRng>,
+) {
+    if game_started_events.iter().next().is_some() {
+        rng.started = true;
+    }
+}
+
+fn advance_tick(game_state: Res<GameState>, mut tick: ResMut<SimulationTick>) {
+    if !matches!(*game_state, GameState::Paused) {
+        tick.0 += 1;
+    }
+}
+
+/// Real time that hasn't been simulated yet, run off in whole ticks.
+#[derive(Default)]
+struct TickClock {
+    last_frame: Option<Instant>,
+    owed: Duration,
+    /// Ticks still to run in the current frame.
+    due: u32,
+}
+
+impl TickClock {
+    /// Number of ticks to run for a frame that started `elapsed` after the previous one.
+    fn ticks_for(&mut self, elapsed: Duration) -> u32 {
+        let tick = Duration::from_secs_f32(1.0 / TICK_RATE);
+        self.owed += elapsed;
+        let ticks = (self.owed.as_nanos() / tick.as_nanos()) as u32;
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.owed = Duration::ZERO;
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.owed -= tick * ticks;
+        ticks
+    }
+}
+
+fn run_due_ticks(mut clock: Local<TickClock>) -> ShouldRun {
+    if clock.due == 0 {
+        let now = Instant::now();
+        let elapsed = match clock.last_frame {
+            Some(last_frame) => now - last_frame,
+            // Run the first frame straight away
+            None => Duration::from_secs_f32(1.0 / TICK_RATE),
+        };
+        clock.last_frame = Some(now);
+        clock.due = clock.ticks_for(elapsed);
+        if clock.due == 0 {
+            return ShouldRun::No;
+        }
+    }
+
+    clock.due -= 1;
+    if clock.due == 0 {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::YesAndCheckAgain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(clock: &mut TickClock, frame_rate: f32, frames: u32) -> u32 {
+        let frame = Duration::from_secs_f32(1.0 / frame_rate);
+        (0..frames).map(|_| clock.ticks_for(frame)).sum()
+    }
+
+    #[test]
+    fn the_tick_rate_does_not_depend_on_the_frame_rate() {
+        for frame_rate in [30.0, 60.0, 144.0, 240.0] {
+            let mut clock = TickClock::default();
+            let ticks = ticks(&mut clock, frame_rate, frame_rate as u32 * 10);
+            assert!(
+                (599..=600).contains(&ticks),
+                "{} ticks at {} Hz",
+                ticks,
+                frame_rate
+            );
+        }
+    }
+
+    #[test]
+    fn stalls_are_not_caught_up_on() {
+        let mut clock = TickClock::default();
+        assert_eq!(clock.ticks_for(Duration::from_secs(2)), MAX_TICKS_PER_FRAME);
+        assert_eq!(ticks(&mut clock, 60.0, 1), 1);
+    }
+}
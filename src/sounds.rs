@@ -1,35 +1,95 @@
+use std::path::PathBuf;
+
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioSource};
+use serde::{Deserialize, Serialize};
+
+use crate::{game_state::GameState, storage, DiedEvent, FlapEvent, IncreaseScoreEvent};
 
-use crate::{DiedEvent, FlapEvent, IncreaseScoreEvent};
+const VOLUME: f32 = 0.3;
 
 pub struct SoundsPlugin;
 
 impl Plugin for SoundsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(prepare_audio)
+        app.insert_resource(SoundSettings::load())
+            .add_startup_system(prepare_audio)
+            .add_system(apply_sound_settings)
+            .add_system(pause_audio)
             .add_system(handle_died)
             .add_system(handle_flap)
             .add_system(handle_point);
     }
 }
 
+/// Whether sound is on, saved between sessions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub enabled: bool,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings { enabled: true }
+    }
+}
+
+impl SoundSettings {
+    /// Loads the settings file, with sound on if there is none.
+    pub fn load() -> Self {
+        settings_path()
+            .as_deref()
+            .map(storage::load_or_default)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = settings_path() {
+            if let Err(err) = storage::save(&path, self) {
+                error!("failed to save sound settings: {}", err);
+            }
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    storage::config_path("sound.ron")
+}
+
 struct AudioState {
     hit: Handle<AudioSource>,
     wing: Handle<AudioSource>,
     point: Handle<AudioSource>,
 }
 
-fn prepare_audio(mut commands: Commands, asset_server: ResMut<AssetServer>, audio: Res<Audio>) {
+fn prepare_audio(mut commands: Commands, asset_server: ResMut<AssetServer>) {
     let hit = asset_server.load("sounds/hit.mp3");
     let wing = asset_server.load("sounds/wing.mp3");
     let point = asset_server.load("sounds/point.mp3");
     let audio_state = AudioState { hit, wing, point };
-    audio.set_volume(0.3);
 
     commands.insert_resource(audio_state);
 }
 
+fn apply_sound_settings(sound_settings: Res<SoundSettings>, audio: Res<Audio>) {
+    if sound_settings.is_changed() {
+        audio.set_volume(if sound_settings.enabled { VOLUME } else { 0.0 });
+    }
+}
+
+fn pause_audio(game_state: Res<GameState>, audio: Res<Audio>, mut paused: Local<bool>) {
+    let is_paused = matches!(*game_state, GameState::Paused);
+    if is_paused != *paused {
+        *paused = is_paused;
+        if is_paused {
+            audio.pause();
+        } else {
+            audio.resume();
+        }
+    }
+}
+
 fn handle_died(
     mut died_events: EventReader<DiedEvent>,
     audio: Res<Audio>,
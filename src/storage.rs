@@ -0,0 +1,73 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Returns the path of `file_name` inside the game's directory in the platform data directory.
+pub fn data_path(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("flappy-burd").join(file_name))
+}
+
+/// Returns the path of `file_name` inside the game's directory in the platform config directory.
+pub fn config_path(file_name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("flappy-burd").join(file_name))
+}
+
+/// Returns the path of `path` inside the assets folder, resolved the same way as the
+/// [`AssetServer`] does.
+pub fn asset_path(path: &str) -> PathBuf {
+    let root = std::env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .ok()
+        .or_else(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        })
+        .unwrap_or_default();
+    root.join("assets").join(path)
+}
+
+/// Loads a RON file, falling back to the default value if it's missing or can't be read.
+pub fn load_or_default<T: DeserializeOwned + Default>(path: &Path) -> T {
+    match fs::read_to_string(path) {
+        Ok(contents) => ron::de::from_str(&contents).unwrap_or_else(|err| {
+            warn!("ignoring corrupt file {}: {}", path.display(), err);
+            T::default()
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => T::default(),
+        Err(err) => {
+            warn!("failed to read {}: {}", path.display(), err);
+            T::default()
+        }
+    }
+}
+
+/// Saves `value` as RON, creating any missing parent directories.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = ron::ser::to_string_pretty(value, Default::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, contents)
+}
+
+/// A file in the temp folder, unique to the test and process.
+#[cfg(test)]
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("flappy-burd-{}-{}.ron", name, std::process::id()))
+}
+
+/// Writes `contents` to a temp file, loads it back with `load` and removes the file again.
+#[cfg(test)]
+pub fn load_temp<T>(name: &str, contents: &str, load: impl FnOnce(&Path) -> T) -> T {
+    let path = temp_path(name);
+    fs::write(&path, contents).unwrap();
+    let loaded = load(&path);
+    fs::remove_file(&path).unwrap();
+    loaded
+}
@@ -0,0 +1,522 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    background::BACKGROUND_CONFIG_PATH,
+    config::ConfigError,
+    progress::{Progress, Unlock},
+    storage,
+};
+
+/// Path of the theme manifest, relative to the assets folder.
+pub const THEMES_PATH: &str = "config/themes.ron";
+
+/// Loads the [`ThemeManifest`] and keeps the [`Theme`] in line with the player's choice.
+///
+/// Locked items are never used, so the [`crate::progress::ProgressPlugin`] should be added first.
+/// Without it only the items that are always available can be chosen.
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        // Inserted up front, so the bird, pipes and ground are spawned with the chosen theme
+        let manifest = ThemeManifest::load_or_default();
+        let settings = ThemeSettings::load();
+        let progress = app
+            .world
+            .get_resource::<Progress>()
+            .cloned()
+            .unwrap_or_default();
+        let theme = Theme::select(&manifest, &settings, &progress);
+        app.insert_resource(ClearColor(theme.world.clear_color()))
+            .insert_resource(theme)
+            .insert_resource(settings)
+            .insert_resource(manifest)
+            .add_system(apply_theme_settings)
+            .add_system(apply_clear_color);
+    }
+}
+
+/// The bird skins and world themes to choose from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeManifest {
+    pub birds: Vec<BirdSkin>,
+    pub worlds: Vec<WorldTheme>,
+}
+
+impl Default for ThemeManifest {
+    fn default() -> Self {
+        ThemeManifest {
+            birds: vec![BirdSkin::default()],
+            worlds: vec![WorldTheme::default()],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BirdSkin {
+    /// Shown in the settings, and saved to remember the choice.
+    pub name: String,
+    /// Sprite sheet with the wings up, resting, wings down and dead frames side by side.
+    pub sprite_sheet: String,
+    /// Size of each frame of the sprite sheet, in pixels.
+    pub frame_width: f32,
+    pub frame_height: f32,
+    /// Size the bird is drawn at.
+    pub width: f32,
+    pub height: f32,
+    /// Half the width and height of the collider, before its corners are rounded.
+    pub collider_half_width: f32,
+    pub collider_half_height: f32,
+    /// Radius the collider's corners are rounded by.
+    #[serde(default)]
+    pub collider_radius: f32,
+    /// Milestone that unlocks the skin, or `None` if it's always available.
+    #[serde(default)]
+    pub unlock: Option<Unlock>,
+}
+
+impl Default for BirdSkin {
+    fn default() -> Self {
+        BirdSkin {
+            name: "Red".to_string(),
+            sprite_sheet: "sprites/birds/red.png".to_string(),
+            frame_width: 136.0,
+            frame_height: 96.0,
+            width: 32.0,
+            height: 24.0,
+            collider_half_width: 8.0,
+            collider_half_height: 5.0,
+            collider_radius: 6.0,
+            unlock: None,
+        }
+    }
+}
+
+/// The look of the pipes, ground and background.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorldTheme {
+    /// Shown in the settings, and saved to remember the choice.
+    pub name: String,
+    /// Pipe image, drawn with its cap at the gap.
+    pub pipe: String,
+    /// Ground image, repeated along the ground.
+    pub ground: String,
+    /// Width of each tile of the ground image.
+    pub ground_tile_width: f32,
+    /// Background layers config, relative to the assets folder.
+    #[serde(default = "default_background")]
+    pub background: String,
+    /// Colour shown behind the background, as red, green and blue between 0 and 1.
+    pub clear_color: (f32, f32, f32),
+    /// Milestone that unlocks the theme, or `None` if it's always available.
+    #[serde(default)]
+    pub unlock: Option<Unlock>,
+}
+
+fn default_background() -> String {
+    BACKGROUND_CONFIG_PATH.to_string()
+}
+
+impl Default for WorldTheme {
+    fn default() -> Self {
+        WorldTheme {
+            name: "Day".to_string(),
+            pipe: "sprites/pipe.png".to_string(),
+            ground: "sprites/ground.png".to_string(),
+            ground_tile_width: 32.0,
+            background: default_background(),
+            clear_color: (0.658, 0.8, 1.0),
+            unlock: None,
+        }
+    }
+}
+
+impl WorldTheme {
+    pub fn clear_color(&self) -> Color {
+        let (red, green, blue) = self.clear_color;
+        Color::rgb(red, green, blue)
+    }
+}
+
+impl ThemeManifest {
+    /// Loads and validates the manifest from the assets folder.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::from_ron(&fs::read_to_string(storage::asset_path(THEMES_PATH))?)
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, ConfigError> {
+        let manifest: ThemeManifest = ron::de::from_str(contents)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Loads the manifest, logging any error and falling back to the built-in theme.
+    pub fn load_or_default() -> Self {
+        ThemeManifest::load().unwrap_or_else(|err| {
+            warn!("using default theme: {}", err);
+            ThemeManifest::default()
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.birds.is_empty() || self.worlds.is_empty() {
+            return Err(ConfigError::Invalid(
+                "themes need at least one bird and one world".to_string(),
+            ));
+        }
+
+        for (i, bird) in self.birds.iter().enumerate() {
+            if self.birds[..i].iter().any(|other| other.name == bird.name) {
+                return Err(ConfigError::Invalid(format!(
+                    "there is more than one bird named {}",
+                    bird.name
+                )));
+            }
+            let positive = [
+                ("frame_width", bird.frame_width),
+                ("frame_height", bird.frame_height),
+                ("width", bird.width),
+                ("height", bird.height),
+                ("collider_half_width", bird.collider_half_width),
+                ("collider_half_height", bird.collider_half_height),
+            ];
+            for (name, value) in positive {
+                if !(value.is_finite() && value > 0.0) {
+                    return Err(ConfigError::Invalid(format!(
+                        "bird {} must have a {} above zero, got {}",
+                        bird.name, name, value
+                    )));
+                }
+            }
+            if !(bird.collider_radius.is_finite() && bird.collider_radius >= 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "bird {} must not have a negative collider_radius",
+                    bird.name
+                )));
+            }
+        }
+
+        for (i, world) in self.worlds.iter().enumerate() {
+            if self.worlds[..i]
+                .iter()
+                .any(|other| other.name == world.name)
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "there is more than one world named {}",
+                    world.name
+                )));
+            }
+            if !(world.ground_tile_width.is_finite() && world.ground_tile_width > 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "world {} must have a ground_tile_width above zero",
+                    world.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The first bird after the one named `current` that passes `available`, wrapping around.
+    pub fn next_bird(
+        &self,
+        current: &str,
+        available: impl Fn(&BirdSkin) -> bool,
+    ) -> Option<&BirdSkin> {
+        let start = self.birds.iter().position(|bird| bird.name == current);
+        next_after(&self.birds, start, available)
+    }
+
+    /// The first world after the one named `current` that passes `available`, wrapping around.
+    pub fn next_world(
+        &self,
+        current: &str,
+        available: impl Fn(&WorldTheme) -> bool,
+    ) -> Option<&WorldTheme> {
+        let start = self.worlds.iter().position(|world| world.name == current);
+        next_after(&self.worlds, start, available)
+    }
+}
+
+fn next_after<T>(items: &[T], start: Option<usize>, available: impl Fn(&T) -> bool) -> Option<&T> {
+    let first = start.map_or(0, |start| start + 1);
+    (first..first + items.len())
+        .map(|index| &items[index % items.len()])
+        .find(|item| available(item))
+}
+
+/// Names of the chosen bird skin and world theme, saved between sessions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub bird: String,
+    pub world: String,
+}
+
+impl ThemeSettings {
+    /// Loads the choice from the settings file, or chooses the first of each if there is none.
+    pub fn load() -> Self {
+        settings_path()
+            .as_deref()
+            .map(storage::load_or_default)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = settings_path() {
+            if let Err(err) = storage::save(&path, self) {
+                error!("failed to save theme settings: {}", err);
+            }
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    storage::config_path("theme.ron")
+}
+
+/// The bird skin and world theme in use.
+///
+/// Headless apps keep the defaults, which match the built-in theme.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Theme {
+    pub bird: BirdSkin,
+    pub world: WorldTheme,
+}
+
+impl Theme {
+    /// The chosen bird and world from the manifest, or the first unlocked one of each if the
+    /// choice is gone or locked.
+    pub fn select(manifest: &ThemeManifest, settings: &ThemeSettings, progress: &Progress) -> Self {
+        let bird = manifest
+            .birds
+            .iter()
+            .find(|bird| bird.name == settings.bird && progress.bird_unlocked(bird))
+            .or_else(|| {
+                manifest
+                    .birds
+                    .iter()
+                    .find(|bird| progress.bird_unlocked(bird))
+            })
+            .or_else(|| manifest.birds.first())
+            .cloned()
+            .unwrap_or_default();
+        let world = manifest
+            .worlds
+            .iter()
+            .find(|world| world.name == settings.world && progress.world_unlocked(world))
+            .or_else(|| {
+                manifest
+                    .worlds
+                    .iter()
+                    .find(|world| progress.world_unlocked(world))
+            })
+            .or_else(|| manifest.worlds.first())
+            .cloned()
+            .unwrap_or_default();
+        Theme { bird, world }
+    }
+}
+
+fn apply_theme_settings(
+    manifest: Res<ThemeManifest>,
+    settings: Res<ThemeSettings>,
+    progress: Option<Res<Progress>>,
+    mut theme: ResMut<Theme>,
+) {
+    let progress_changed = progress
+        .as_ref()
+        .map_or(false, |progress| progress.is_changed());
+    if settings.is_changed() || manifest.is_changed() || progress_changed {
+        let no_progress = Progress::default();
+        let progress = progress.as_deref().unwrap_or(&no_progress);
+        let selected = Theme::select(&manifest, &settings, progress);
+        // Only changes are applied, so nothing is respawned needlessly
+        if *theme != selected {
+            *theme = selected;
+        }
+    }
+}
+
+fn apply_clear_color(theme: Res<Theme>, mut clear_color: ResMut<ClearColor>) {
+    if theme.is_changed() {
+        clear_color.0 = theme.world.clear_color();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bird(name: &str) -> BirdSkin {
+        BirdSkin {
+            name: name.to_string(),
+            ..BirdSkin::default()
+        }
+    }
+
+    fn world(name: &str) -> WorldTheme {
+        WorldTheme {
+            name: name.to_string(),
+            ..WorldTheme::default()
+        }
+    }
+
+    fn manifest(birds: &[&str], worlds: &[&str]) -> ThemeManifest {
+        ThemeManifest {
+            birds: birds.iter().map(|name| bird(name)).collect(),
+            worlds: worlds.iter().map(|name| world(name)).collect(),
+        }
+    }
+
+    #[test]
+    fn the_shipped_themes_are_valid() {
+        let manifest = ThemeManifest::load().unwrap();
+        // Something has to be available before any milestone is reached
+        assert_eq!(manifest.birds[0].unlock, None);
+        assert_eq!(manifest.worlds[0].unlock, None);
+    }
+
+    #[test]
+    fn themes_need_a_bird_and_a_world() {
+        assert!(manifest(&["Red"], &["Day"]).validate().is_ok());
+        assert!(manifest(&[], &["Day"]).validate().is_err());
+        assert!(manifest(&["Red"], &[]).validate().is_err());
+    }
+
+    #[test]
+    fn names_are_unique() {
+        assert!(manifest(&["Red", "Red"], &["Day"]).validate().is_err());
+        assert!(manifest(&["Red"], &["Day", "Day"]).validate().is_err());
+        // Birds and worlds are chosen separately, so they may share a name
+        assert!(manifest(&["Day"], &["Day"]).validate().is_ok());
+    }
+
+    #[test]
+    fn sizes_have_to_be_positive() {
+        let mut themes = manifest(&["Red"], &["Day"]);
+        themes.birds[0].width = 0.0;
+        assert!(themes.validate().is_err());
+
+        let mut themes = manifest(&["Red"], &["Day"]);
+        themes.birds[0].collider_half_height = f32::NAN;
+        assert!(themes.validate().is_err());
+
+        let mut themes = manifest(&["Red"], &["Day"]);
+        themes.birds[0].collider_radius = 0.0;
+        assert!(themes.validate().is_ok());
+        themes.birds[0].collider_radius = -1.0;
+        assert!(themes.validate().is_err());
+
+        let mut themes = manifest(&["Red"], &["Day"]);
+        themes.worlds[0].ground_tile_width = 0.0;
+        assert!(themes.validate().is_err());
+    }
+
+    #[test]
+    fn next_wraps_around_and_skips_unavailable_items() {
+        let themes = manifest(&["Red", "Blue", "Green"], &["Day", "Night"]);
+        let name = |bird: Option<&BirdSkin>| bird.map(|bird| bird.name.clone());
+
+        assert_eq!(name(themes.next_bird("Red", |_| true)), Some("Blue".into()));
+        assert_eq!(
+            name(themes.next_bird("Green", |_| true)),
+            Some("Red".into())
+        );
+        assert_eq!(
+            name(themes.next_bird("Red", |bird| bird.name != "Blue")),
+            Some("Green".into())
+        );
+        // The current item comes round again if it's the only one available
+        assert_eq!(
+            name(themes.next_bird("Red", |bird| bird.name == "Red")),
+            Some("Red".into())
+        );
+        assert_eq!(
+            themes
+                .next_world("Day", |_| true)
+                .map(|world| world.name.as_str()),
+            Some("Night")
+        );
+    }
+
+    #[test]
+    fn next_starts_from_the_first_item_if_the_current_one_is_gone() {
+        let themes = manifest(&["Red", "Blue"], &["Day"]);
+        assert_eq!(
+            themes
+                .next_bird("Purple", |_| true)
+                .map(|bird| bird.name.as_str()),
+            Some("Red")
+        );
+    }
+
+    #[test]
+    fn next_finds_nothing_if_everything_is_locked() {
+        let themes = manifest(&["Red", "Blue"], &["Day"]);
+        assert_eq!(themes.next_bird("Red", |_| false), None);
+        assert_eq!(themes.next_world("Day", |_| false), None);
+    }
+
+    #[test]
+    fn selection_falls_back_to_the_first_item_when_the_saved_one_is_gone() {
+        let themes = manifest(&["Red", "Blue"], &["Day", "Night"]);
+        let settings = |bird: &str, world: &str| ThemeSettings {
+            bird: bird.to_string(),
+            world: world.to_string(),
+        };
+
+        let theme = Theme::select(&themes, &settings("Blue", "Night"), &Progress::default());
+        assert_eq!(
+            (theme.bird.name.as_str(), theme.world.name.as_str()),
+            ("Blue", "Night")
+        );
+
+        let theme = Theme::select(&themes, &settings("Purple", "Dusk"), &Progress::default());
+        assert_eq!(
+            (theme.bird.name.as_str(), theme.world.name.as_str()),
+            ("Red", "Day")
+        );
+
+        let theme = Theme::select(&themes, &ThemeSettings::default(), &Progress::default());
+        assert_eq!(
+            (theme.bird.name.as_str(), theme.world.name.as_str()),
+            ("Red", "Day")
+        );
+    }
+
+    #[test]
+    fn locked_choices_fall_back_to_the_first_unlocked_item() {
+        let mut themes = manifest(&["Red", "Blue", "Green"], &["Day", "Night"]);
+        themes.birds[0].unlock = Some(Unlock::Score(50));
+        themes.birds[1].unlock = Some(Unlock::Score(10));
+        themes.worlds[1].unlock = Some(Unlock::Score(25));
+        let settings = ThemeSettings {
+            bird: "Blue".to_string(),
+            world: "Night".to_string(),
+        };
+
+        let theme = Theme::select(&themes, &settings, &Progress::default());
+        assert_eq!(
+            (theme.bird.name.as_str(), theme.world.name.as_str()),
+            ("Green", "Day")
+        );
+
+        let progress = Progress {
+            birds: ["Blue".to_string()].into_iter().collect(),
+            worlds: ["Night".to_string()].into_iter().collect(),
+            ..Progress::default()
+        };
+        let theme = Theme::select(&themes, &settings, &progress);
+        assert_eq!(
+            (theme.bird.name.as_str(), theme.world.name.as_str()),
+            ("Blue", "Night")
+        );
+    }
+}
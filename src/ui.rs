@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+/// Text in a single style.
+pub fn text(value: String, font: &Handle<Font>, font_size: f32, color: Color) -> Text {
+    Text::with_section(
+        value,
+        TextStyle {
+            font: font.clone(),
+            font_size,
+            color,
+        },
+        Default::default(),
+    )
+}
+
+/// A line of text in a panel or overlay, spaced out from the lines around it.
+pub fn panel_line(value: String, font: &Handle<Font>, font_size: f32, color: Color) -> TextBundle {
+    TextBundle {
+        text: text(value, font, font_size, color),
+        style: Style {
+            margin: Rect::all(Val::Px(8.0)),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Spawns a box of `lines` in the middle of the screen, from top to bottom. The whole panel is
+/// tagged with `marker` to find it again.
+pub fn spawn_panel(commands: &mut Commands, marker: impl Component, lines: Vec<TextBundle>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        padding: Rect::all(Val::Px(24.0)),
+                        ..Default::default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for line in lines {
+                        parent.spawn_bundle(line);
+                    }
+                });
+        });
+}
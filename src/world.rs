@@ -1,51 +1,152 @@
 use bevy::prelude::*;
 use heron::prelude::*;
 
-use crate::{Layer, WIN_HEIGHT, WIN_WIDTH};
+use crate::{
+    background::{spawn_tiles, TiledStrip},
+    camera::Playfield,
+    config::GameConfig,
+    theme::Theme,
+    Layer,
+};
+
+/// Height of the ground and ceiling colliders.
+const BOUND_HEIGHT: f32 = 20.0;
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_ground);
+        app.add_startup_system(setup_ground)
+            .add_system(fit_bounds)
+            .add_system(spawn_ground_strip)
+            .add_system(apply_gravity);
     }
 }
 
-fn setup_ground(mut commands: Commands) {
+#[derive(Component)]
+struct Ground;
+
+#[derive(Component)]
+struct Ceiling;
+
+/// The row of textured tiles drawn over the ground's collider, scrolled at the pipes' speed by
+/// the background.
+#[derive(Component)]
+struct GroundStrip;
+
+fn setup_ground(mut commands: Commands, config: Res<GameConfig>) {
+    // Resized by `fit_bounds` whenever the config or window changes
     commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::rgb(0.02, 0.674, 0.188),
-                custom_size: Some(Vec2::new(WIN_WIDTH, 20.0)),
-                ..Default::default()
-            },
-            transform: Transform::from_xyz(0.0, -(WIN_HEIGHT / 2.0) + 2.0, 0.0),
-            ..Default::default()
-        })
+        .spawn_bundle((
+            Transform::from_xyz(0.0, -(config.window_height / 2.0) + 2.0, 0.0),
+            GlobalTransform::default(),
+        ))
         .insert(RigidBody::Static)
-        .insert(CollisionShape::Cuboid {
-            half_extends: Vec3::new(WIN_WIDTH / 2.0, 10.0, 0.0),
-            border_radius: None,
-        })
+        .insert(bound_shape(&config))
         .insert(
             CollisionLayers::none()
                 .with_group(Layer::World)
                 .with_mask(Layer::Player),
-        );
+        )
+        .insert(Ground);
 
     commands
         .spawn_bundle((
-            Transform::from_xyz(0.0, WIN_HEIGHT / 2.0, 0.0),
+            Transform::from_xyz(0.0, config.window_height / 2.0, 0.0),
             GlobalTransform::default(),
         ))
         .insert(RigidBody::Static)
-        .insert(CollisionShape::Cuboid {
-            half_extends: Vec3::new(WIN_WIDTH / 2.0, 10.0, 0.0),
-            border_radius: None,
-        })
+        .insert(bound_shape(&config))
         .insert(
             CollisionLayers::none()
                 .with_group(Layer::World)
                 .with_mask(Layer::Player),
-        );
+        )
+        .insert(Ceiling);
+}
+
+/// Collider of the ground and ceiling, spanning the logical playfield.
+fn bound_shape(config: &GameConfig) -> CollisionShape {
+    CollisionShape::Cuboid {
+        half_extends: Vec3::new(config.window_width / 2.0, BOUND_HEIGHT / 2.0, 0.0),
+        border_radius: None,
+    }
+}
+
+/// Keeps the ground and ceiling at the edges of the logical playfield.
+fn fit_bounds(
+    config: Res<GameConfig>,
+    mut ground: Query<(&mut Transform, &mut CollisionShape), (With<Ground>, Without<Ceiling>)>,
+    mut ceiling: Query<(&mut Transform, &mut CollisionShape), With<Ceiling>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    for (mut transform, mut shape) in ground.iter_mut() {
+        transform.translation.y = -(config.window_height / 2.0) + 2.0;
+        *shape = bound_shape(&config);
+    }
+    for (mut transform, mut shape) in ceiling.iter_mut() {
+        transform.translation.y = config.window_height / 2.0;
+        *shape = bound_shape(&config);
+    }
+}
+
+/// Covers the ground with enough tiles to span the window, again whenever it's resized.
+fn spawn_ground_strip(
+    mut commands: Commands,
+    asset_server: Option<Res<AssetServer>>,
+    config: Res<GameConfig>,
+    theme: Res<Theme>,
+    playfield: Option<Res<Playfield>>,
+    ground: Query<Entity, With<Ground>>,
+    strips: Query<Entity, With<GroundStrip>>,
+) {
+    // Headless apps only need the collider
+    let (asset_server, playfield) = match (asset_server, playfield) {
+        (Some(asset_server), Some(playfield)) => (asset_server, playfield),
+        _ => return,
+    };
+    if !config.is_changed() && !playfield.is_changed() && !theme.is_changed() {
+        return;
+    }
+
+    for entity in strips.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let visible_width = playfield.visible.x.max(config.window_width);
+    let tile_width = theme.world.ground_tile_width;
+    let texture = asset_server.load(theme.world.ground.as_str());
+    for ground in ground.iter() {
+        commands.entity(ground).with_children(|parent| {
+            parent
+                .spawn_bundle((
+                    // In front of the bottom pipes, which reach down into the ground
+                    Transform::from_xyz(0.0, 0.0, 1.0),
+                    GlobalTransform::default(),
+                ))
+                .insert(GroundStrip)
+                .insert(TiledStrip {
+                    speed: 1.0,
+                    tile_width,
+                })
+                .with_children(|parent| {
+                    spawn_tiles(
+                        parent,
+                        &texture,
+                        Vec2::new(tile_width, BOUND_HEIGHT),
+                        Color::WHITE,
+                        visible_width,
+                    );
+                });
+        });
+    }
+}
+
+fn apply_gravity(mut commands: Commands, config: Res<GameConfig>) {
+    if config.is_changed() {
+        commands.insert_resource(Gravity::from(Vec3::new(0.0, -config.gravity, 0.0)));
+    }
 }
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use flappy_burd::{
+    autopilot::Autopilot,
+    bird::Bird,
+    camera::Playfield,
+    controls::{ActionInput, InputAction},
+    game_state::GameState,
+    headless_app,
+    pipes::PipePair,
+    score::Score,
+    simulation::{GameRng, SimulationTick},
+    GameResetEvent,
+};
+
+/// Longest a run is simulated for, a minute at the fixed tick rate.
+const MAX_TICKS: u64 = 60 * 60;
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    score: u32,
+    /// Tick the bird died on, or `None` if it was still flying after [`MAX_TICKS`].
+    death_tick: Option<u64>,
+    bird_y: f32,
+}
+
+/// Plays a run in `app` until the bird dies, flapping whenever `flap` returns true for the tick
+/// and the bird's height.
+fn play(app: &mut App, flap: impl Fn(u64, f32) -> bool) -> Outcome {
+    // Runs the startup systems
+    app.update();
+
+    let mut death_tick = None;
+    for _ in 0..MAX_TICKS {
+        let tick = app.world.get_resource::<SimulationTick>().unwrap().0;
+        if flap(tick, bird_y(app)) {
+            app.world
+                .get_resource_mut::<ActionInput>()
+                .unwrap()
+                .press(InputAction::Flap);
+        }
+        app.update();
+
+        if matches!(app.world.get_resource::<GameState>(), Some(GameState::Dead)) {
+            death_tick = Some(tick);
+            break;
+        }
+    }
+
+    let world = &mut app.world;
+    let score = world.query::<&Score>().iter(world).next().unwrap().0;
+    Outcome {
+        score,
+        death_tick,
+        bird_y: bird_y(app),
+    }
+}
+
+fn bird_y(app: &mut App) -> f32 {
+    let world = &mut app.world;
+    world
+        .query_filtered::<&Transform, With<Bird>>()
+        .iter(world)
+        .next()
+        .unwrap()
+        .translation
+        .y
+}
+
+/// Keeps the bird level with the first pipe pair's gap, which is always centred, until it's
+/// through, then stops flapping and lets it fall.
+fn fly_through_the_first_pipe(tick: u64, bird_y: f32) -> bool {
+    tick == 0 || (tick < 4 * 60 && bird_y < -25.0)
+}
+
+fn autopilot_app(seed: u64) -> App {
+    let mut app = headless_app(seed);
+    app.insert_resource(Autopilot { enabled: true });
+    app
+}
+
+#[test]
+fn same_seed_and_flaps_give_the_same_run() {
+    let first = play(&mut headless_app(42), fly_through_the_first_pipe);
+    assert!(first.death_tick.is_some(), "{:?}", first);
+    assert!(first.score > 0, "{:?}", first);
+
+    let second = play(&mut headless_app(42), fly_through_the_first_pipe);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn same_seed_gives_the_same_autopilot_run() {
+    let first = play(&mut autopilot_app(7), |_, _| false);
+    assert!(first.score > 0, "{:?}", first);
+
+    let second = play(&mut autopilot_app(7), |_, _| false);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn different_seeds_give_different_autopilot_runs() {
+    let first = play(&mut autopilot_app(7), |_, _| false);
+    let second = play(&mut autopilot_app(8), |_, _| false);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn window_width_does_not_change_the_run() {
+    let narrow = play(&mut autopilot_app(7), |_, _| false);
+
+    // Wide windows place pipe pairs further ahead of the bird
+    let mut wide = autopilot_app(7);
+    wide.insert_resource(Playfield {
+        scale: 1.0,
+        visible: Vec2::new(3000.0, 600.0),
+    });
+    let wide = play(&mut wide, |_, _| false);
+
+    assert_eq!(narrow, wide);
+}
+
+/// A menu choice for [`press_menu`] to act on in the next update.
+#[derive(Default)]
+struct MenuPress(Option<MenuChoice>);
+
+#[derive(Clone, Copy)]
+enum MenuChoice {
+    ReturnToMenu,
+    Play,
+}
+
+/// Changes the game state and resets the course like the menu does, without any ordering against
+/// the pipes, as the menu's systems have none.
+fn press_menu(
+    mut press: ResMut<MenuPress>,
+    mut game_state: ResMut<GameState>,
+    mut game_reset_events: EventWriter<GameResetEvent>,
+) {
+    if let Some(choice) = press.0.take() {
+        *game_state = match choice {
+            MenuChoice::ReturnToMenu => GameState::Menu,
+            MenuChoice::Play => GameState::Waiting,
+        };
+        game_reset_events.send(GameResetEvent);
+    }
+}
+
+/// Positions of the pipe pairs in the course, left to right.
+fn pipe_positions(app: &mut App) -> Vec<Vec2> {
+    let world = &mut app.world;
+    let mut positions: Vec<Vec2> = world
+        .query_filtered::<&Transform, With<PipePair>>()
+        .iter(world)
+        .map(|transform| transform.translation.truncate())
+        // Pairs that aren't part of the course are parked far off to the left
+        .filter(|position| position.x > -10_000.0)
+        .collect();
+    positions.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+    positions
+}
+
+#[test]
+fn playing_from_the_menu_gives_the_course_of_the_run_seed() {
+    let mut app = headless_app(42);
+    app.init_resource::<MenuPress>().add_system(press_menu);
+    play(&mut app, |tick, _| tick == 0);
+
+    for choice in [MenuChoice::ReturnToMenu, MenuChoice::Play] {
+        app.world.get_resource_mut::<MenuPress>().unwrap().0 = Some(choice);
+        app.update();
+    }
+    let run_seed = app.world.get_resource::<GameRng>().unwrap().run_seed();
+    assert_eq!(run_seed, 43);
+
+    let mut fresh = headless_app(run_seed);
+    fresh.update();
+    assert_eq!(pipe_positions(&mut app), pipe_positions(&mut fresh));
+}
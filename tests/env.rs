@@ -0,0 +1,99 @@
+use flappy_burd::{
+    env::{Action, FlappyEnv, DEATH_REWARD},
+    game_state::GameState,
+};
+
+/// More than enough steps for a bird that never flaps to hit the ground.
+const MAX_STEPS: usize = 600;
+
+/// Steps without flapping until the run is over, returning the rewards of every step.
+fn fall(env: &mut FlappyEnv) -> Vec<f32> {
+    let mut rewards = Vec::new();
+    for _ in 0..MAX_STEPS {
+        let (_, reward, done) = env.step(Action::NoFlap);
+        rewards.push(reward);
+        if done {
+            return rewards;
+        }
+    }
+    panic!("the bird was still flying after {} steps", MAX_STEPS);
+}
+
+#[test]
+fn new_envs_are_already_playing() {
+    let env = FlappyEnv::new(1);
+    assert!(!env.is_done());
+    assert!(matches!(
+        env.app().world.get_resource::<GameState>(),
+        Some(GameState::Playing)
+    ));
+}
+
+#[test]
+fn falling_ends_the_run_with_the_death_reward() {
+    let mut env = FlappyEnv::new(1);
+    let rewards = fall(&mut env);
+
+    assert_eq!(rewards.last(), Some(&DEATH_REWARD));
+    assert!(rewards[..rewards.len() - 1]
+        .iter()
+        .all(|reward| *reward == 0.0));
+    assert!(env.is_done());
+}
+
+#[test]
+fn reset_starts_the_same_run_as_a_new_env() {
+    let mut env = FlappyEnv::new(1);
+    fall(&mut env);
+
+    let observation = env.reset(9);
+    assert!(!env.is_done());
+    assert_eq!(observation, FlappyEnv::new(9).observe());
+}
+
+#[test]
+fn finished_runs_wait_for_a_reset() {
+    let mut env = FlappyEnv::new(1);
+    fall(&mut env);
+    let last = env.observe();
+
+    assert_eq!(env.step(Action::Flap), (last, 0.0, true));
+    assert!(matches!(
+        env.app().world.get_resource::<GameState>(),
+        Some(GameState::Dead)
+    ));
+}
+
+#[test]
+fn runs_after_a_reset_play_out_like_in_a_new_env() {
+    let mut env = FlappyEnv::new(1);
+    fall(&mut env);
+    env.reset(3);
+    let mut new = FlappyEnv::new(3);
+
+    for step in 0..300 {
+        let action = if step % 40 == 0 {
+            Action::Flap
+        } else {
+            Action::NoFlap
+        };
+        assert_eq!(env.step(action), new.step(action), "step {}", step);
+    }
+}
+
+#[test]
+fn same_seed_and_actions_give_the_same_steps() {
+    let actions = (0..300).map(|step| {
+        if step % 40 == 0 {
+            Action::Flap
+        } else {
+            Action::NoFlap
+        }
+    });
+    let mut first = FlappyEnv::new(3);
+    let mut second = FlappyEnv::new(3);
+
+    for action in actions {
+        assert_eq!(first.step(action), second.step(action));
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use flappy_burd::{
+    bird::Bird,
+    game_state::GameState,
+    headless_app,
+    replay::{Replay, ReplayPlugin},
+    simulation::SimulationTick,
+};
+use heron::PhysicsTime;
+
+/// Ticks each playback is simulated for.
+const TICKS: u64 = 120;
+
+/// Plays `replay` back headlessly and returns where the bird ended up, pausing for a while
+/// before simulating the tick `pause_at`, if given.
+fn play_back(replay: &Replay, pause_at: Option<u64>) -> f32 {
+    let mut app = headless_app(replay.seed);
+    app.add_plugin(ReplayPlugin::Playback(replay.clone()));
+
+    let mut paused = false;
+    while app.world.get_resource::<SimulationTick>().unwrap().0 < TICKS {
+        let tick = app.world.get_resource::<SimulationTick>().unwrap().0;
+        if pause_at == Some(tick) && !paused {
+            paused = true;
+            set_paused(&mut app, true);
+            for _ in 0..10 {
+                app.update();
+            }
+            set_paused(&mut app, false);
+        }
+        app.update();
+    }
+
+    let world = &mut app.world;
+    world
+        .query_filtered::<&Transform, With<Bird>>()
+        .iter(world)
+        .next()
+        .unwrap()
+        .translation
+        .y
+}
+
+/// Pauses or resumes the game the way the pause key does.
+fn set_paused(app: &mut App, paused: bool) {
+    let mut physics_time = app.world.get_resource_mut::<PhysicsTime>().unwrap();
+    if paused {
+        physics_time.pause();
+    } else {
+        physics_time.resume();
+    }
+    let game_state = if paused {
+        GameState::Paused
+    } else {
+        GameState::Playing
+    };
+    app.world.insert_resource(game_state);
+}
+
+#[test]
+fn pausing_across_a_recorded_flap_keeps_the_flap() {
+    let replay = Replay {
+        flaps: vec![1, 30, 60],
+        ..Replay::new(5)
+    };
+
+    let uninterrupted = play_back(&replay, None);
+    assert_eq!(play_back(&replay, Some(30)), uninterrupted);
+
+    // The flap matters, so losing it would have been noticed
+    let without_flap = Replay {
+        flaps: vec![1, 60],
+        ..replay
+    };
+    assert_ne!(play_back(&without_flap, None), uninterrupted);
+}